@@ -1,42 +1,110 @@
-use std::marker::PhantomData;
+#[cfg(windows)]
 use std::ptr;
 
-use winapi::shared::minwindef::DWORD;
-use winapi::um::accctrl::{self, EXPLICIT_ACCESS_W, PEXPLICIT_ACCESS_W, TRUSTEE_W};
-use winapi::um::winnt::{self, ACE_HEADER, PSID};
+#[cfg(windows)]
+use winapi::um::accctrl::{self, EXPLICIT_ACCESS_W, TRUSTEE_W};
 
-use super::super::SecurityIdPtr;
+use bytes::{read_u16, read_u32};
+use guid::Guid;
+
+use super::super::{SecurityId, SecurityIdPtr, TrustLabel};
+use super::mandatory_label::{MandatoryLabel, MandatoryPolicy};
+use super::resource_attribute::{ClaimSecurityAttribute, InvalidClaimAttributeError};
+
+pub const ACE_HEADER_SIZE: usize = 4;
+pub const ACE_OBJECT_TYPE_PRESENT: u32 = 0x1;
+pub const ACE_INHERITED_OBJECT_TYPE_PRESENT: u32 = 0x2;
+
+const ACCESS_ALLOWED_ACE_TYPE: u8 = 0x0;
+const ACCESS_DENIED_ACE_TYPE: u8 = 0x1;
+const SYSTEM_AUDIT_ACE_TYPE: u8 = 0x2;
+const SYSTEM_ALARM_ACE_TYPE: u8 = 0x3;
+const ACCESS_ALLOWED_OBJECT_ACE_TYPE: u8 = 0x5;
+const ACCESS_DENIED_OBJECT_ACE_TYPE: u8 = 0x6;
+const SYSTEM_AUDIT_OBJECT_ACE_TYPE: u8 = 0x7;
+const SYSTEM_ALARM_OBJECT_ACE_TYPE: u8 = 0x8;
+const ACCESS_ALLOWED_CALLBACK_ACE_TYPE: u8 = 0x9;
+const ACCESS_DENIED_CALLBACK_ACE_TYPE: u8 = 0xA;
+const ACCESS_ALLOWED_CALLBACK_OBJECT_ACE_TYPE: u8 = 0xB;
+const ACCESS_DENIED_CALLBACK_OBJECT_ACE_TYPE: u8 = 0xC;
+const SYSTEM_AUDIT_CALLBACK_ACE_TYPE: u8 = 0xD;
+const SYSTEM_ALARM_CALLBACK_ACE_TYPE: u8 = 0xE;
+const SYSTEM_AUDIT_CALLBACK_OBJECT_ACE_TYPE: u8 = 0xF;
+const SYSTEM_ALARM_CALLBACK_OBJECT_ACE_TYPE: u8 = 0x10;
+const SYSTEM_MANDATORY_LABEL_ACE_TYPE: u8 = 0x11;
+const SYSTEM_RESOURCE_ATTRIBUTE_ACE_TYPE: u8 = 0x12;
+const SYSTEM_SCOPED_POLICY_ID_ACE_TYPE: u8 = 0x13;
+const SYSTEM_PROCESS_TRUST_LABEL_ACE_TYPE: u8 = 0x14;
+
+const FLAGS_OFFSET: usize = 1;
+const MASK_OFFSET: usize = ACE_HEADER_SIZE;
+const SID_OFFSET: usize = MASK_OFFSET + 4;
+const OBJECT_FLAGS_OFFSET: usize = MASK_OFFSET + 4;
+const OBJECT_TYPE_OFFSET: usize = OBJECT_FLAGS_OFFSET + 4;
+const GUID_SIZE: usize = 16;
 
 bitflags! {
     pub struct AccessMask: u32 {
-        const DELETE = winnt::DELETE;
-        const READ_CONTROL = winnt::READ_CONTROL;
-        const WRITE_DAC = winnt::WRITE_DAC;
-        const WRITE_OWNER = winnt::WRITE_OWNER;
-        const SYNCHRONIZE = winnt::SYNCHRONIZE;
-        const ACCESS_SYSTEM_SECURITY = winnt::ACCESS_SYSTEM_SECURITY;
-        const MAXIMUM_ALLOWED = winnt::MAXIMUM_ALLOWED;
-        const GENERIC_ALL = winnt::GENERIC_ALL;
-        const GENERIC_EXECUTE = winnt::GENERIC_EXECUTE;
-        const GENERIC_WRITE = winnt::GENERIC_WRITE;
-        const GENERIC_READ = winnt::GENERIC_READ;
+        const DELETE = 0x0001_0000;
+        const READ_CONTROL = 0x0002_0000;
+        const WRITE_DAC = 0x0004_0000;
+        const WRITE_OWNER = 0x0008_0000;
+        const SYNCHRONIZE = 0x0010_0000;
+        const ACCESS_SYSTEM_SECURITY = 0x0100_0000;
+        const MAXIMUM_ALLOWED = 0x0200_0000;
+        const GENERIC_ALL = 0x1000_0000;
+        const GENERIC_EXECUTE = 0x2000_0000;
+        const GENERIC_WRITE = 0x4000_0000;
+        const GENERIC_READ = 0x8000_0000;
+
+        const SPECIFIC_RIGHTS_ALL = 0x0000_FFFF;
+        const STANDARD_RIGHTS_REQUIRED = 0x000F_0000;
+        const STANDARD_RIGHTS_ALL = 0x001F_0000;
     }
 }
 
 bitflags! {
     pub struct AccessInheritance: u32 {
-        const CONTAINERS_INHERIT = winnt::CONTAINER_INHERIT_ACE as u32;
-        const INHERIT_ONLY = winnt::INHERIT_ONLY_ACE as u32;
-        const DONT_PROPAGATE = winnt::NO_PROPAGATE_INHERIT_ACE as u32;
-        const OBJECTS_INHERIT = winnt::OBJECT_INHERIT_ACE as u32;
+        const CONTAINERS_INHERIT = 0x2;
+        const INHERIT_ONLY = 0x8;
+        const DONT_PROPAGATE = 0x4;
+        const OBJECTS_INHERIT = 0x1;
 
-        const CONTAINERS_AND_OBJECTS_INHERIT = accctrl::SUB_CONTAINERS_AND_OBJECTS_INHERIT;
-        const NO_INHERITANCE = accctrl::NO_INHERITANCE;
-        const ONLY_CONTAINERS_INHERIT = accctrl::SUB_CONTAINERS_ONLY_INHERIT;
-        const ONLY_OBJECTS_INHERIT = accctrl::SUB_OBJECTS_ONLY_INHERIT;
+        const CONTAINERS_AND_OBJECTS_INHERIT = 0x3;
+        const NO_INHERITANCE = 0x0;
+        const ONLY_CONTAINERS_INHERIT = 0x2;
+        const ONLY_OBJECTS_INHERIT = 0x1;
     }
 }
 
+bitflags! {
+    pub struct AceFlags: u8 {
+        const OBJECT_INHERIT = 0x01;
+        const CONTAINER_INHERIT = 0x02;
+        const NO_PROPAGATE_INHERIT = 0x04;
+        const INHERIT_ONLY = 0x08;
+        const INHERITED = 0x10;
+        const CRITICAL = 0x20;
+        const SUCCESSFUL_ACCESS = 0x40;
+        const FAILED_ACCESS = 0x80;
+    }
+}
+
+impl AccessMask {
+    /// Keeps bits that have no named flag, so a decoded mask encodes back to the same bytes.
+    pub fn from_raw(bits: u32) -> Self {
+        unsafe { AccessMask::from_bits_unchecked(bits) }
+    }
+}
+
+impl AceFlags {
+    /// Keeps bits that have no named flag, so decoded flags encode back to the same byte.
+    pub fn from_raw(bits: u8) -> Self {
+        unsafe { AceFlags::from_bits_unchecked(bits) }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[repr(u32)]
 pub enum AccessMode {
     NotUsed = 0,
@@ -48,79 +116,123 @@ pub enum AccessMode {
     SetAuditFailure,
 }
 
+#[derive(Clone, Copy)]
 pub struct AccessControlEntryPtr<'a> {
-    ace: AcePtr,
-    _ptr_lifetime: PhantomData<&'a ()>,
+    ace: AcePtr<'a>,
 }
 
-enum AcePtr {
-    AccessAllowed(*const winnt::ACCESS_ALLOWED_ACE),
-    AccessAllowedCallback(*const winnt::ACCESS_ALLOWED_CALLBACK_ACE),
-    AccessAllowedCallbackObject(*const winnt::ACCESS_ALLOWED_CALLBACK_OBJECT_ACE),
-    AccessAllowedObject(*const winnt::ACCESS_ALLOWED_OBJECT_ACE),
-    AccessDenied(*const winnt::ACCESS_DENIED_ACE),
-    AccessDeniedCallback(*const winnt::ACCESS_DENIED_CALLBACK_ACE),
-    AccessDeniedCallbackObject(*const winnt::ACCESS_DENIED_CALLBACK_OBJECT_ACE),
-    AccessDeniedObject(*const winnt::ACCESS_DENIED_OBJECT_ACE),
-    Unknown(*const ACE_HEADER),
+#[derive(Clone, Copy)]
+enum AcePtr<'a> {
+    AccessAllowed(&'a [u8]),
+    AccessAllowedCallback(&'a [u8]),
+    AccessAllowedCallbackObject(&'a [u8]),
+    AccessAllowedObject(&'a [u8]),
+    AccessDenied(&'a [u8]),
+    AccessDeniedCallback(&'a [u8]),
+    AccessDeniedCallbackObject(&'a [u8]),
+    AccessDeniedObject(&'a [u8]),
+    SystemAlarm(&'a [u8]),
+    SystemAlarmCallback(&'a [u8]),
+    SystemAlarmCallbackObject(&'a [u8]),
+    SystemAlarmObject(&'a [u8]),
+    SystemAudit(&'a [u8]),
+    SystemAuditCallback(&'a [u8]),
+    SystemAuditCallbackObject(&'a [u8]),
+    SystemAuditObject(&'a [u8]),
+    SystemMandatoryLabel(&'a [u8]),
+    SystemResourceAttribute(&'a [u8]),
+    SystemScopedPolicyId(&'a [u8]),
+    SystemProcessTrustLabel(&'a [u8]),
+    Unknown(&'a [u8]),
 }
 
 impl<'a> AccessControlEntryPtr<'a> {
-    pub unsafe fn new(ace_ptr: *const ACE_HEADER) -> Self {
+    pub(crate) fn from_prefix(bytes: &'a [u8]) -> Option<Self> {
+        let size = read_u16(bytes, 2)? as usize;
+
+        if size < ACE_HEADER_SIZE {
+            return None;
+        }
+
+        bytes.get(..size).map(Self::new)
+    }
+
+    fn new(ace_bytes: &'a [u8]) -> Self {
         use self::AcePtr::*;
 
-        let ace = match (*ace_ptr).AceType {
-            winnt::ACCESS_ALLOWED_ACE_TYPE => {
-                AccessAllowed(ace_ptr as *const winnt::ACCESS_ALLOWED_ACE)
-            }
-            winnt::ACCESS_ALLOWED_CALLBACK_ACE_TYPE => {
-                AccessAllowedCallback(ace_ptr as *const winnt::ACCESS_ALLOWED_CALLBACK_ACE)
-            }
-            winnt::ACCESS_ALLOWED_CALLBACK_OBJECT_ACE_TYPE => AccessAllowedCallbackObject(
-                ace_ptr as *const winnt::ACCESS_ALLOWED_CALLBACK_OBJECT_ACE,
-            ),
-            winnt::ACCESS_ALLOWED_OBJECT_ACE_TYPE => {
-                AccessAllowedObject(ace_ptr as *const winnt::ACCESS_ALLOWED_OBJECT_ACE)
-            }
-            winnt::ACCESS_DENIED_ACE_TYPE => {
-                AccessDenied(ace_ptr as *const winnt::ACCESS_DENIED_ACE)
-            }
-            winnt::ACCESS_DENIED_CALLBACK_ACE_TYPE => {
-                AccessDeniedCallback(ace_ptr as *const winnt::ACCESS_DENIED_CALLBACK_ACE)
-            }
-            winnt::ACCESS_DENIED_CALLBACK_OBJECT_ACE_TYPE => AccessDeniedCallbackObject(
-                ace_ptr as *const winnt::ACCESS_DENIED_CALLBACK_OBJECT_ACE,
-            ),
-            winnt::ACCESS_DENIED_OBJECT_ACE_TYPE => {
-                AccessDeniedObject(ace_ptr as *const winnt::ACCESS_DENIED_OBJECT_ACE)
-            }
-            _ => Unknown(ace_ptr),
+        let ace = match ace_bytes[0] {
+            ACCESS_ALLOWED_ACE_TYPE => AccessAllowed(ace_bytes),
+            ACCESS_ALLOWED_CALLBACK_ACE_TYPE => AccessAllowedCallback(ace_bytes),
+            ACCESS_ALLOWED_CALLBACK_OBJECT_ACE_TYPE => AccessAllowedCallbackObject(ace_bytes),
+            ACCESS_ALLOWED_OBJECT_ACE_TYPE => AccessAllowedObject(ace_bytes),
+            ACCESS_DENIED_ACE_TYPE => AccessDenied(ace_bytes),
+            ACCESS_DENIED_CALLBACK_ACE_TYPE => AccessDeniedCallback(ace_bytes),
+            ACCESS_DENIED_CALLBACK_OBJECT_ACE_TYPE => AccessDeniedCallbackObject(ace_bytes),
+            ACCESS_DENIED_OBJECT_ACE_TYPE => AccessDeniedObject(ace_bytes),
+            SYSTEM_ALARM_ACE_TYPE => SystemAlarm(ace_bytes),
+            SYSTEM_ALARM_CALLBACK_ACE_TYPE => SystemAlarmCallback(ace_bytes),
+            SYSTEM_ALARM_CALLBACK_OBJECT_ACE_TYPE => SystemAlarmCallbackObject(ace_bytes),
+            SYSTEM_ALARM_OBJECT_ACE_TYPE => SystemAlarmObject(ace_bytes),
+            SYSTEM_AUDIT_ACE_TYPE => SystemAudit(ace_bytes),
+            SYSTEM_AUDIT_CALLBACK_ACE_TYPE => SystemAuditCallback(ace_bytes),
+            SYSTEM_AUDIT_CALLBACK_OBJECT_ACE_TYPE => SystemAuditCallbackObject(ace_bytes),
+            SYSTEM_AUDIT_OBJECT_ACE_TYPE => SystemAuditObject(ace_bytes),
+            SYSTEM_MANDATORY_LABEL_ACE_TYPE => SystemMandatoryLabel(ace_bytes),
+            SYSTEM_RESOURCE_ATTRIBUTE_ACE_TYPE => SystemResourceAttribute(ace_bytes),
+            SYSTEM_SCOPED_POLICY_ID_ACE_TYPE => SystemScopedPolicyId(ace_bytes),
+            SYSTEM_PROCESS_TRUST_LABEL_ACE_TYPE => SystemProcessTrustLabel(ace_bytes),
+            _ => Unknown(ace_bytes),
         };
 
-        AccessControlEntryPtr {
-            ace,
-            _ptr_lifetime: PhantomData,
-        }
+        AccessControlEntryPtr { ace }
     }
 
     pub fn access_mask(&self) -> Option<AccessMask> {
         use self::AcePtr::*;
 
-        unsafe {
-            let raw_access_mask = match self.ace {
-                AccessAllowed(ace) => (*ace).Mask,
-                AccessAllowedCallback(ace) => (*ace).Mask,
-                AccessAllowedCallbackObject(ace) => (*ace).Mask,
-                AccessAllowedObject(ace) => (*ace).Mask,
-                AccessDenied(ace) => (*ace).Mask,
-                AccessDeniedCallback(ace) => (*ace).Mask,
-                AccessDeniedCallbackObject(ace) => (*ace).Mask,
-                AccessDeniedObject(ace) => (*ace).Mask,
-                Unknown(_) => return None,
-            };
+        let raw_access_mask = match self.ace {
+            AccessAllowed(ace)
+            | AccessAllowedCallback(ace)
+            | AccessAllowedCallbackObject(ace)
+            | AccessAllowedObject(ace)
+            | AccessDenied(ace)
+            | AccessDeniedCallback(ace)
+            | AccessDeniedCallbackObject(ace)
+            | AccessDeniedObject(ace)
+            | SystemAlarm(ace)
+            | SystemAlarmCallback(ace)
+            | SystemAlarmCallbackObject(ace)
+            | SystemAlarmObject(ace)
+            | SystemAudit(ace)
+            | SystemAuditCallback(ace)
+            | SystemAuditCallbackObject(ace)
+            | SystemAuditObject(ace)
+            | SystemMandatoryLabel(ace)
+            | SystemResourceAttribute(ace)
+            | SystemScopedPolicyId(ace)
+            | SystemProcessTrustLabel(ace) => read_u32(ace, MASK_OFFSET)?,
+            Unknown(_) => return None,
+        };
 
-            Some(AccessMask::from_bits_truncate(raw_access_mask))
-        }
+        Some(AccessMask::from_raw(raw_access_mask))
+    }
+
+    pub fn flags(&self) -> AceFlags {
+        AceFlags::from_raw(self.as_bytes()[FLAGS_OFFSET])
+    }
+
+    pub fn is_inherited(&self) -> bool {
+        self.flags().contains(AceFlags::INHERITED)
+    }
+
+    pub fn inheritance(&self) -> AccessInheritance {
+        let inheritance_flags = self.flags()
+            & (AceFlags::OBJECT_INHERIT
+                | AceFlags::CONTAINER_INHERIT
+                | AceFlags::NO_PROPAGATE_INHERIT
+                | AceFlags::INHERIT_ONLY);
+
+        AccessInheritance::from_bits_truncate(u32::from(inheritance_flags.bits()))
     }
 
     pub fn grants_access(&self) -> Option<bool> {
@@ -135,81 +247,492 @@ impl<'a> AccessControlEntryPtr<'a> {
             | AccessDeniedCallback(_)
             | AccessDeniedCallbackObject(_)
             | AccessDeniedObject(_) => Some(false),
-            Unknown(_) => None,
+            _ => None,
         }
     }
 
-    pub fn trustee<'b>(&'b self) -> Option<SecurityIdPtr<'b>> {
+    pub fn is_audit(&self) -> bool {
+        use self::AcePtr::*;
+
+        matches!(
+            self.ace,
+            SystemAudit(_)
+                | SystemAuditCallback(_)
+                | SystemAuditCallbackObject(_)
+                | SystemAuditObject(_)
+        )
+    }
+
+    pub fn is_alarm(&self) -> bool {
         use self::AcePtr::*;
 
-        unsafe {
-            let sid_start: *const DWORD = match self.ace {
-                AccessAllowed(ace) => &(*ace).SidStart,
-                AccessAllowedCallback(ace) => &(*ace).SidStart,
-                AccessAllowedCallbackObject(ace) => &(*ace).SidStart,
-                AccessAllowedObject(ace) => &(*ace).SidStart,
-                AccessDenied(ace) => &(*ace).SidStart,
-                AccessDeniedCallback(ace) => &(*ace).SidStart,
-                AccessDeniedCallbackObject(ace) => &(*ace).SidStart,
-                AccessDeniedObject(ace) => &(*ace).SidStart,
-                Unknown(_) => return None,
-            };
+        matches!(
+            self.ace,
+            SystemAlarm(_)
+                | SystemAlarmCallback(_)
+                | SystemAlarmCallbackObject(_)
+                | SystemAlarmObject(_)
+        )
+    }
+
+    pub fn audits_success(&self) -> bool {
+        (self.is_audit() || self.is_alarm()) && self.flags().contains(AceFlags::SUCCESSFUL_ACCESS)
+    }
+
+    pub fn audits_failure(&self) -> bool {
+        (self.is_audit() || self.is_alarm()) && self.flags().contains(AceFlags::FAILED_ACCESS)
+    }
+
+    pub fn mandatory_label(&self) -> Option<MandatoryLabel> {
+        let ace = match self.ace {
+            AcePtr::SystemMandatoryLabel(ace) => ace,
+            _ => return None,
+        };
+        let policy = MandatoryPolicy::from_bits_truncate(read_u32(ace, MASK_OFFSET)?);
+        let label = SecurityIdPtr::from_prefix(ace.get(SID_OFFSET..)?)?;
 
-            Some(SecurityIdPtr::new(sid_start as PSID))
+        if !label.is_integrity_label() {
+            return None;
         }
+
+        Some(MandatoryLabel::from_rid(
+            label.relative_identifier()?,
+            policy,
+        ))
     }
 
-    pub fn size(&self) -> usize {
+    pub fn resource_attribute(
+        &self,
+    ) -> Result<Option<ClaimSecurityAttribute>, InvalidClaimAttributeError> {
+        match self.ace {
+            AcePtr::SystemResourceAttribute(_) => {}
+            _ => return Ok(None),
+        }
+
+        let attribute_data = self
+            .application_data()
+            .ok_or(InvalidClaimAttributeError::Truncated)?;
+
+        ClaimSecurityAttribute::from_bytes(attribute_data).map(Some)
+    }
+
+    pub fn scoped_policy_id(&self) -> Option<SecurityIdPtr<'a>> {
+        match self.ace {
+            AcePtr::SystemScopedPolicyId(_) => self.trustee(),
+            _ => None,
+        }
+    }
+
+    pub fn trust_label(&self) -> Option<TrustLabel> {
+        match self.ace {
+            AcePtr::SystemProcessTrustLabel(_) => self.trustee()?.trust_label(),
+            _ => None,
+        }
+    }
+
+    pub fn object_type(&self) -> Option<Guid> {
+        let ace = self.object_ace()?;
+
+        if read_u32(ace, OBJECT_FLAGS_OFFSET)? & ACE_OBJECT_TYPE_PRESENT == 0 {
+            return None;
+        }
+
+        Guid::from_prefix(ace.get(OBJECT_TYPE_OFFSET..)?)
+    }
+
+    pub fn inherited_object_type(&self) -> Option<Guid> {
+        let ace = self.object_ace()?;
+        let object_flags = read_u32(ace, OBJECT_FLAGS_OFFSET)?;
+
+        if object_flags & ACE_INHERITED_OBJECT_TYPE_PRESENT == 0 {
+            return None;
+        }
+
+        let offset = if object_flags & ACE_OBJECT_TYPE_PRESENT != 0 {
+            OBJECT_TYPE_OFFSET + GUID_SIZE
+        } else {
+            OBJECT_TYPE_OFFSET
+        };
+
+        Guid::from_prefix(ace.get(offset..)?)
+    }
+
+    fn object_ace(&self) -> Option<&'a [u8]> {
+        use self::AcePtr::*;
+
+        match self.ace {
+            AccessAllowedCallbackObject(ace)
+            | AccessAllowedObject(ace)
+            | AccessDeniedCallbackObject(ace)
+            | AccessDeniedObject(ace)
+            | SystemAlarmCallbackObject(ace)
+            | SystemAlarmObject(ace)
+            | SystemAuditCallbackObject(ace)
+            | SystemAuditObject(ace) => Some(ace),
+            _ => None,
+        }
+    }
+
+    pub fn trustee(&self) -> Option<SecurityIdPtr<'a>> {
+        let (ace, sid_offset) = self.trustee_offset()?;
+
+        SecurityIdPtr::from_prefix(ace.get(sid_offset..)?)
+    }
+
+    pub fn application_data(&self) -> Option<&'a [u8]> {
+        use self::AcePtr::*;
+
+        match self.ace {
+            AccessAllowedCallback(_)
+            | AccessAllowedCallbackObject(_)
+            | AccessDeniedCallback(_)
+            | AccessDeniedCallbackObject(_)
+            | SystemAlarmCallback(_)
+            | SystemAlarmCallbackObject(_)
+            | SystemAuditCallback(_)
+            | SystemAuditCallbackObject(_)
+            | SystemResourceAttribute(_) => {}
+            _ => return None,
+        }
+
+        let (ace, sid_offset) = self.trustee_offset()?;
+        let trustee = SecurityIdPtr::from_prefix(ace.get(sid_offset..)?)?;
+
+        ace.get(sid_offset + trustee.as_bytes().len()..)
+    }
+
+    fn trustee_offset(&self) -> Option<(&'a [u8], usize)> {
         use self::AcePtr::*;
 
-        unsafe {
-            let header = match self.ace {
-                AccessAllowed(ace) => &(*ace).Header,
-                AccessAllowedCallback(ace) => &(*ace).Header,
-                AccessAllowedCallbackObject(ace) => &(*ace).Header,
-                AccessAllowedObject(ace) => &(*ace).Header,
-                AccessDenied(ace) => &(*ace).Header,
-                AccessDeniedCallback(ace) => &(*ace).Header,
-                AccessDeniedCallbackObject(ace) => &(*ace).Header,
-                AccessDeniedObject(ace) => &(*ace).Header,
-                Unknown(ace_header) => &(*ace_header),
-            };
+        let (ace, sid_offset) = match self.ace {
+            AccessAllowed(ace)
+            | AccessAllowedCallback(ace)
+            | AccessDenied(ace)
+            | AccessDeniedCallback(ace)
+            | SystemAlarm(ace)
+            | SystemAlarmCallback(ace)
+            | SystemAudit(ace)
+            | SystemAuditCallback(ace)
+            | SystemMandatoryLabel(ace)
+            | SystemResourceAttribute(ace)
+            | SystemScopedPolicyId(ace)
+            | SystemProcessTrustLabel(ace) => (ace, SID_OFFSET),
+            AccessAllowedCallbackObject(ace)
+            | AccessAllowedObject(ace)
+            | AccessDeniedCallbackObject(ace)
+            | AccessDeniedObject(ace)
+            | SystemAlarmCallbackObject(ace)
+            | SystemAlarmObject(ace)
+            | SystemAuditCallbackObject(ace)
+            | SystemAuditObject(ace) => (ace, object_sid_offset(ace)?),
+            Unknown(_) => return None,
+        };
+
+        Some((ace, sid_offset))
+    }
 
-            header.AceSize as usize
+    pub fn size(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        use self::AcePtr::*;
+
+        match self.ace {
+            AccessAllowed(ace)
+            | AccessAllowedCallback(ace)
+            | AccessAllowedCallbackObject(ace)
+            | AccessAllowedObject(ace)
+            | AccessDenied(ace)
+            | AccessDeniedCallback(ace)
+            | AccessDeniedCallbackObject(ace)
+            | AccessDeniedObject(ace)
+            | SystemAlarm(ace)
+            | SystemAlarmCallback(ace)
+            | SystemAlarmCallbackObject(ace)
+            | SystemAlarmObject(ace)
+            | SystemAudit(ace)
+            | SystemAuditCallback(ace)
+            | SystemAuditCallbackObject(ace)
+            | SystemAuditObject(ace)
+            | SystemMandatoryLabel(ace)
+            | SystemResourceAttribute(ace)
+            | SystemScopedPolicyId(ace)
+            | SystemProcessTrustLabel(ace)
+            | Unknown(ace) => ace,
         }
     }
 }
 
+fn object_sid_offset(ace: &[u8]) -> Option<usize> {
+    let object_flags = read_u32(ace, OBJECT_FLAGS_OFFSET)?;
+    let mut offset = OBJECT_TYPE_OFFSET;
+
+    if object_flags & ACE_OBJECT_TYPE_PRESENT != 0 {
+        offset += GUID_SIZE;
+    }
+
+    if object_flags & ACE_INHERITED_OBJECT_TYPE_PRESENT != 0 {
+        offset += GUID_SIZE;
+    }
+
+    Some(offset)
+}
+
 pub struct ExplicitAccess<'trustee> {
-    explicit_access: EXPLICIT_ACCESS_W,
-    _trustee_lifetime: PhantomData<&'trustee ()>,
+    permissions: AccessMask,
+    mode: AccessMode,
+    inheritance: AccessInheritance,
+    trustee: Trustee<'trustee>,
+}
+
+enum Trustee<'a> {
+    Borrowed(SecurityIdPtr<'a>),
+    Owned(SecurityId),
 }
 
 impl<'trustee> ExplicitAccess<'trustee> {
-    pub fn new<T: AsRef<SecurityIdPtr<'trustee>>>(
+    pub fn new<T: Into<SecurityIdPtr<'trustee>>>(
         permissions: AccessMask,
         mode: AccessMode,
         inheritance: AccessInheritance,
         trustee: T,
     ) -> Self {
         ExplicitAccess {
-            explicit_access: EXPLICIT_ACCESS_W {
-                grfAccessPermissions: permissions.bits(),
-                grfAccessMode: mode as u32,
-                grfInheritance: inheritance.bits(),
-                Trustee: TRUSTEE_W {
-                    pMultipleTrustee: ptr::null_mut(),
-                    MultipleTrusteeOperation: accctrl::NO_MULTIPLE_TRUSTEE,
-                    TrusteeForm: accctrl::TRUSTEE_IS_SID,
-                    TrusteeType: accctrl::TRUSTEE_IS_UNKNOWN,
-                    ptstrName: unsafe { trustee.as_ref().as_ptr() as *mut _ },
-                },
+            permissions,
+            mode,
+            inheritance,
+            trustee: Trustee::Borrowed(trustee.into()),
+        }
+    }
+
+    pub fn with_owned_trustee(
+        permissions: AccessMask,
+        mode: AccessMode,
+        inheritance: AccessInheritance,
+        trustee: SecurityId,
+    ) -> Self {
+        ExplicitAccess {
+            permissions,
+            mode,
+            inheritance,
+            trustee: Trustee::Owned(trustee),
+        }
+    }
+
+    pub fn permissions(&self) -> AccessMask {
+        self.permissions
+    }
+
+    pub fn mode(&self) -> AccessMode {
+        self.mode
+    }
+
+    pub fn inheritance(&self) -> AccessInheritance {
+        self.inheritance
+    }
+
+    pub fn trustee<'a>(&'a self) -> SecurityIdPtr<'a> {
+        match self.trustee {
+            Trustee::Borrowed(trustee) => trustee,
+            Trustee::Owned(ref trustee) => trustee.as_sid_ptr(),
+        }
+    }
+
+    #[cfg(windows)]
+    pub fn to_raw(&self) -> EXPLICIT_ACCESS_W {
+        EXPLICIT_ACCESS_W {
+            grfAccessPermissions: self.permissions.bits(),
+            grfAccessMode: self.mode as u32,
+            grfInheritance: self.inheritance.bits(),
+            Trustee: TRUSTEE_W {
+                pMultipleTrustee: ptr::null_mut(),
+                MultipleTrusteeOperation: accctrl::NO_MULTIPLE_TRUSTEE,
+                TrusteeForm: accctrl::TRUSTEE_IS_SID,
+                TrusteeType: accctrl::TRUSTEE_IS_UNKNOWN,
+                ptstrName: self.trustee().as_ptr() as *mut _,
             },
-            _trustee_lifetime: PhantomData,
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCAL_SYSTEM: [u8; 12] = [1, 1, 0, 0, 0, 0, 0, 5, 18, 0, 0, 0];
+
+    fn entry(ace_type: u8, flags: u8, body: &[u8]) -> Vec<u8> {
+        let size = ACE_HEADER_SIZE + body.len();
+        let mut bytes = vec![ace_type, flags, size as u8, (size >> 8) as u8];
+
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    fn plain_entry(ace_type: u8, flags: u8) -> Vec<u8> {
+        let mut body = AccessMask::GENERIC_READ.bits().to_le_bytes().to_vec();
+
+        body.extend_from_slice(&LOCAL_SYSTEM);
+        entry(ace_type, flags, &body)
+    }
+
+    fn object_entry(
+        object_type: Option<[u8; 16]>,
+        inherited_object_type: Option<[u8; 16]>,
+    ) -> Vec<u8> {
+        let mut object_flags = 0;
+        let mut body = AccessMask::GENERIC_READ.bits().to_le_bytes().to_vec();
+        let mut guids = Vec::new();
+
+        if let Some(object_type) = object_type {
+            object_flags |= ACE_OBJECT_TYPE_PRESENT;
+            guids.extend_from_slice(&object_type);
+        }
+
+        if let Some(inherited_object_type) = inherited_object_type {
+            object_flags |= ACE_INHERITED_OBJECT_TYPE_PRESENT;
+            guids.extend_from_slice(&inherited_object_type);
+        }
+
+        body.extend_from_slice(&object_flags.to_le_bytes());
+        body.extend_from_slice(&guids);
+        body.extend_from_slice(&LOCAL_SYSTEM);
+        entry(ACCESS_ALLOWED_OBJECT_ACE_TYPE, 0, &body)
+    }
+
+    #[test]
+    fn reads_inheritance_flags() {
+        let inherited = plain_entry(ACCESS_ALLOWED_ACE_TYPE, 0x13);
+        let inherited = AccessControlEntryPtr::from_prefix(&inherited).unwrap();
+        let explicit = plain_entry(ACCESS_ALLOWED_ACE_TYPE, 0x0E);
+        let explicit = AccessControlEntryPtr::from_prefix(&explicit).unwrap();
+        let audit_only = plain_entry(ACCESS_ALLOWED_ACE_TYPE, 0xC0);
+        let audit_only = AccessControlEntryPtr::from_prefix(&audit_only).unwrap();
+
+        assert_eq!(
+            inherited.flags(),
+            AceFlags::OBJECT_INHERIT | AceFlags::CONTAINER_INHERIT | AceFlags::INHERITED
+        );
+        assert!(inherited.is_inherited());
+        assert_eq!(
+            inherited.inheritance(),
+            AccessInheritance::CONTAINERS_AND_OBJECTS_INHERIT
+        );
+
+        assert!(!explicit.is_inherited());
+        assert_eq!(
+            explicit.inheritance(),
+            AccessInheritance::CONTAINERS_INHERIT
+                | AccessInheritance::DONT_PROPAGATE
+                | AccessInheritance::INHERIT_ONLY
+        );
+
+        assert_eq!(
+            audit_only.flags(),
+            AceFlags::SUCCESSFUL_ACCESS | AceFlags::FAILED_ACCESS
+        );
+        assert!(!audit_only.is_inherited());
+        assert_eq!(audit_only.inheritance(), AccessInheritance::NO_INHERITANCE);
+    }
+
+    #[test]
+    fn classifies_audit_and_alarm_entries() {
+        let audits = [
+            SYSTEM_AUDIT_ACE_TYPE,
+            SYSTEM_AUDIT_OBJECT_ACE_TYPE,
+            SYSTEM_AUDIT_CALLBACK_ACE_TYPE,
+            SYSTEM_AUDIT_CALLBACK_OBJECT_ACE_TYPE,
+        ];
+        let alarms = [
+            SYSTEM_ALARM_ACE_TYPE,
+            SYSTEM_ALARM_OBJECT_ACE_TYPE,
+            SYSTEM_ALARM_CALLBACK_ACE_TYPE,
+            SYSTEM_ALARM_CALLBACK_OBJECT_ACE_TYPE,
+        ];
+
+        for &ace_type in audits.iter().chain(&alarms) {
+            let success = plain_entry(ace_type, 0x40);
+            let success = AccessControlEntryPtr::from_prefix(&success).unwrap();
+            let failure = plain_entry(ace_type, 0x80);
+            let failure = AccessControlEntryPtr::from_prefix(&failure).unwrap();
+
+            assert_eq!(success.is_audit(), audits.contains(&ace_type));
+            assert_eq!(success.is_alarm(), alarms.contains(&ace_type));
+            assert_eq!(success.grants_access(), None);
+            assert!(success.audits_success());
+            assert!(!success.audits_failure());
+            assert!(!failure.audits_success());
+            assert!(failure.audits_failure());
+        }
+
+        for &ace_type in &[ACCESS_ALLOWED_ACE_TYPE, ACCESS_DENIED_ACE_TYPE, 0x42] {
+            let bytes = plain_entry(ace_type, 0xC0);
+            let entry = AccessControlEntryPtr::from_prefix(&bytes).unwrap();
+
+            assert!(!entry.is_audit());
+            assert!(!entry.is_alarm());
+            assert!(!entry.audits_success());
+            assert!(!entry.audits_failure());
+        }
+    }
+
+    #[test]
+    fn reads_object_types_before_the_trustee() {
+        let object_type = [
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E,
+            0x0F, 0x10,
+        ];
+        let inherited_object_type = [
+            0xF0, 0xE0, 0xD0, 0xC0, 0xB0, 0xA0, 0x90, 0x80, 0x70, 0x60, 0x50, 0x40, 0x30, 0x20,
+            0x10, 0x00,
+        ];
+        let combinations = [
+            (None, None),
+            (Some(object_type), None),
+            (None, Some(inherited_object_type)),
+            (Some(object_type), Some(inherited_object_type)),
+        ];
+
+        for &(object_type, inherited_object_type) in &combinations {
+            let bytes = object_entry(object_type, inherited_object_type);
+            let entry = AccessControlEntryPtr::from_prefix(&bytes).unwrap();
+            let guid_count = object_type.iter().count() + inherited_object_type.iter().count();
+
+            assert_eq!(bytes.len(), 4 + 4 + 4 + 16 * guid_count + 12);
+            assert_eq!(entry.object_type(), object_type.map(Guid::from_bytes));
+            assert_eq!(
+                entry.inherited_object_type(),
+                inherited_object_type.map(Guid::from_bytes)
+            );
+            assert_eq!(
+                entry.trustee().map(|trustee| trustee.as_bytes()),
+                Some(&LOCAL_SYSTEM[..])
+            );
+            assert_eq!(entry.access_mask(), Some(AccessMask::GENERIC_READ));
+        }
+    }
+
+    #[test]
+    fn ignores_object_types_on_plain_and_truncated_entries() {
+        let plain = plain_entry(ACCESS_ALLOWED_ACE_TYPE, 0);
+        let plain = AccessControlEntryPtr::from_prefix(&plain).unwrap();
+        let full = object_entry(Some([1; 16]), Some([2; 16]));
+        let truncated = entry(
+            ACCESS_ALLOWED_OBJECT_ACE_TYPE,
+            0,
+            &full[4..4 + 4 + 4 + 16 + 8],
+        );
+        let truncated = AccessControlEntryPtr::from_prefix(&truncated).unwrap();
 
-    pub unsafe fn as_ptr(&self) -> PEXPLICIT_ACCESS_W {
-        (&self.explicit_access as *const EXPLICIT_ACCESS_W) as PEXPLICIT_ACCESS_W
+        assert_eq!(plain.object_type(), None);
+        assert_eq!(plain.inherited_object_type(), None);
+        assert_eq!(
+            plain.trustee().map(|trustee| trustee.as_bytes()),
+            Some(&LOCAL_SYSTEM[..])
+        );
+        assert_eq!(truncated.object_type(), Some(Guid::from_bytes([1; 16])));
+        assert_eq!(truncated.inherited_object_type(), None);
+        assert_eq!(truncated.trustee(), None);
     }
 }
@@ -0,0 +1,348 @@
+use claims::Claims;
+
+use super::super::{SecurityId, SecurityIdPtr};
+use super::ace::{AccessMask, AceFlags};
+use super::condition::ConditionalExpression;
+use super::entry::AccessControlEntry;
+use super::{AccessControlList, AccessControlListPtr};
+
+#[derive(Clone)]
+pub struct CentralAccessRule {
+    name: String,
+    applies_to: Option<ConditionalExpression>,
+    effective: AccessControlList,
+    staged: Option<AccessControlList>,
+}
+
+impl CentralAccessRule {
+    pub fn new(name: &str, effective: AccessControlList) -> Self {
+        CentralAccessRule {
+            name: name.to_owned(),
+            applies_to: None,
+            effective,
+            staged: None,
+        }
+    }
+
+    pub fn with_applies_to(mut self, applies_to: ConditionalExpression) -> Self {
+        self.applies_to = Some(applies_to);
+        self
+    }
+
+    pub fn with_staged(mut self, staged: AccessControlList) -> Self {
+        self.staged = Some(staged);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn applies_to(&self) -> Option<&ConditionalExpression> {
+        self.applies_to.as_ref()
+    }
+
+    pub fn effective<'a>(&'a self) -> AccessControlListPtr<'a> {
+        self.effective.as_acl_ptr()
+    }
+
+    pub fn staged<'a>(&'a self) -> Option<AccessControlListPtr<'a>> {
+        self.staged.as_ref().map(AccessControlList::as_acl_ptr)
+    }
+
+    pub fn is_applicable(&self, claims: &Claims) -> bool {
+        self.applies_to
+            .as_ref()
+            .map_or(true, |condition| condition.evaluate(claims) == Some(true))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CentralAccessResult {
+    effective: AccessMask,
+    staged: AccessMask,
+}
+
+impl CentralAccessResult {
+    pub fn effective(&self) -> AccessMask {
+        self.effective
+    }
+
+    pub fn staged(&self) -> AccessMask {
+        self.staged
+    }
+
+    pub fn has_staging_differences(&self) -> bool {
+        self.effective != self.staged
+    }
+}
+
+#[derive(Clone)]
+pub struct CentralAccessPolicy {
+    id: SecurityId,
+    name: String,
+    rules: Vec<CentralAccessRule>,
+}
+
+impl CentralAccessPolicy {
+    pub fn new(id: SecurityId, name: &str) -> Self {
+        CentralAccessPolicy {
+            id,
+            name: name.to_owned(),
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, rule: CentralAccessRule) {
+        self.rules.push(rule);
+    }
+
+    pub fn id<'a>(&'a self) -> SecurityIdPtr<'a> {
+        self.id.as_sid_ptr()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn rules(&self) -> &[CentralAccessRule] {
+        &self.rules
+    }
+
+    pub fn to_entry(&self, flags: AceFlags) -> AccessControlEntry {
+        AccessControlEntry::scoped_policy_id(self.id.clone(), flags)
+    }
+
+    pub fn is_referenced_by(&self, sacl: AccessControlListPtr) -> bool {
+        sacl.entries().any(|entry| {
+            !entry.flags().contains(AceFlags::INHERIT_ONLY)
+                && entry.scoped_policy_id() == Some(self.id())
+        })
+    }
+
+    pub fn evaluate(&self, claims: &Claims) -> CentralAccessResult {
+        let mut effective = AccessMask::all();
+        let mut staged = AccessMask::all();
+
+        for rule in self.rules.iter().filter(|rule| rule.is_applicable(claims)) {
+            let rule_effective = rule.effective().granted_access(claims);
+            let rule_staged = rule
+                .staged()
+                .map_or(rule_effective, |staged| staged.granted_access(claims));
+
+            effective &= rule_effective;
+            staged &= rule_staged;
+        }
+
+        CentralAccessResult { effective, staged }
+    }
+}
+
+impl AccessControlEntry {
+    pub fn scoped_policy_id(policy_id: SecurityId, flags: AceFlags) -> Self {
+        AccessControlEntry::SystemScopedPolicyId {
+            flags,
+            mask: AccessMask::empty(),
+            policy_id,
+        }
+    }
+}
+
+impl<'a> AccessControlListPtr<'a> {
+    pub fn granted_access(&self, claims: &Claims) -> AccessMask {
+        let groups = claims.user_groups();
+        let mut granted = AccessMask::empty();
+        let mut denied = AccessMask::empty();
+
+        for entry in self.entries() {
+            if entry.flags().contains(AceFlags::INHERIT_ONLY) || !groups.applies_to(&entry) {
+                continue;
+            }
+
+            let mask = match entry.access_mask() {
+                Some(mask) => mask,
+                None => continue,
+            };
+            let condition = entry.application_data().map(|_| {
+                entry
+                    .condition()
+                    .and_then(|condition| condition.evaluate(claims))
+            });
+
+            match entry.grants_access() {
+                Some(true) if condition.map_or(true, |result| result == Some(true)) => {
+                    granted |= mask - denied;
+                }
+                Some(false) if condition.map_or(true, |result| result != Some(false)) => {
+                    denied |= mask - granted;
+                }
+                _ => {}
+            }
+        }
+
+        granted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {AceType, ClaimSource, ClaimValue, GroupAttributes, WellKnownSid};
+
+    const READ: AccessMask = AccessMask::GENERIC_READ;
+    const WRITE: AccessMask = AccessMask::GENERIC_WRITE;
+
+    fn everyone() -> SecurityId {
+        SecurityId::well_known(WellKnownSid::World).unwrap()
+    }
+
+    fn policy_id() -> SecurityId {
+        "S-1-17-3260955821-1180564752-550833841-1617862776"
+            .parse()
+            .unwrap()
+    }
+
+    fn claims() -> Claims {
+        let mut claims = Claims::new();
+
+        claims.set_user_groups(
+            vec![(everyone(), GroupAttributes::ENABLED)]
+                .into_iter()
+                .collect(),
+        );
+        claims.insert(ClaimSource::User, "clearance", vec![ClaimValue::Int64(5)]);
+        claims
+    }
+
+    fn entry(ace_type: AceType, mask: AccessMask, condition: Option<&str>) -> AccessControlEntry {
+        let builder = AccessControlEntry::builder(ace_type)
+            .mask(mask)
+            .trustee(&everyone());
+
+        match condition {
+            Some(condition) => builder.condition(&condition.parse().unwrap()),
+            None => builder,
+        }
+        .build()
+        .unwrap()
+    }
+
+    fn acl(entries: &[AccessControlEntry]) -> AccessControlList {
+        AccessControlList::from_entries(entries).unwrap()
+    }
+
+    #[test]
+    fn an_earlier_deny_wins_over_an_allow() {
+        let acl = acl(&[
+            entry(AceType::AccessDenied, WRITE, None),
+            entry(AceType::AccessAllowed, READ | WRITE, None),
+        ]);
+
+        assert_eq!(acl.as_acl_ptr().granted_access(&claims()), READ);
+    }
+
+    #[test]
+    fn a_later_rule_denies_access_granted_by_an_earlier_rule() {
+        let mut policy = CentralAccessPolicy::new(policy_id(), "Finance");
+
+        policy.push(CentralAccessRule::new(
+            "Everyone",
+            acl(&[entry(AceType::AccessAllowed, READ | WRITE, None)]),
+        ));
+        policy.push(CentralAccessRule::new(
+            "Read only",
+            acl(&[
+                entry(AceType::AccessDenied, WRITE, None),
+                entry(AceType::AccessAllowed, READ | WRITE, None),
+            ]),
+        ));
+
+        let result = policy.evaluate(&claims());
+
+        assert_eq!(result.effective(), READ);
+        assert_eq!(result.staged(), READ);
+        assert!(!result.has_staging_differences());
+    }
+
+    #[test]
+    fn unknown_conditions_neither_grant_nor_deny() {
+        let unknown = Some("(@User.department == \"Finance\")");
+        let ignored = acl(&[
+            entry(AceType::AccessAllowedCallback, WRITE, unknown),
+            entry(AceType::AccessAllowed, READ, None),
+        ]);
+        let granted_later = acl(&[
+            entry(AceType::AccessAllowedCallback, WRITE, unknown),
+            entry(AceType::AccessAllowed, WRITE, None),
+        ]);
+        let satisfied = acl(&[entry(
+            AceType::AccessAllowedCallback,
+            WRITE,
+            Some("(@User.clearance >= 5)"),
+        )]);
+
+        assert_eq!(ignored.as_acl_ptr().granted_access(&claims()), READ);
+        assert_eq!(granted_later.as_acl_ptr().granted_access(&claims()), WRITE);
+        assert_eq!(satisfied.as_acl_ptr().granted_access(&claims()), WRITE);
+    }
+
+    #[test]
+    fn skips_rules_that_do_not_apply() {
+        let read_only = acl(&[entry(AceType::AccessAllowed, READ, None)]);
+        let mut policy = CentralAccessPolicy::new(policy_id(), "Finance");
+
+        policy.push(
+            CentralAccessRule::new(
+                "Everyone",
+                acl(&[entry(AceType::AccessAllowed, READ | WRITE, None)]),
+            )
+            .with_applies_to("(@User.clearance >= 5)".parse().unwrap())
+            .with_staged(read_only.clone()),
+        );
+        policy.push(
+            CentralAccessRule::new("False", read_only.clone())
+                .with_applies_to("(@User.clearance > 5)".parse().unwrap()),
+        );
+        policy.push(
+            CentralAccessRule::new("Unknown", read_only)
+                .with_applies_to("(@User.department == \"Finance\")".parse().unwrap()),
+        );
+
+        let result = policy.evaluate(&claims());
+
+        assert!(policy.rules()[0].is_applicable(&claims()));
+        assert!(!policy.rules()[1].is_applicable(&claims()));
+        assert!(!policy.rules()[2].is_applicable(&claims()));
+        assert_eq!(result.effective(), READ | WRITE);
+        assert_eq!(result.staged(), READ);
+        assert!(result.has_staging_differences());
+    }
+
+    #[test]
+    fn references_policies_by_scoped_policy_id() {
+        let policy = CentralAccessPolicy::new(policy_id(), "Finance");
+        let entry = policy.to_entry(AceFlags::OBJECT_INHERIT);
+        let bytes = entry.to_bytes().unwrap();
+        let inherit_only = policy.to_entry(AceFlags::INHERIT_ONLY);
+        let other = CentralAccessPolicy::new("S-1-17-1".parse().unwrap(), "Other");
+
+        assert_eq!(bytes[0], AceType::SystemScopedPolicyId as u8);
+        assert_eq!(AccessControlEntry::from_bytes(&bytes).unwrap(), entry);
+
+        let sacl = acl(&[entry]);
+        let parsed = sacl.as_acl_ptr().entries().next().unwrap();
+
+        assert_eq!(parsed.scoped_policy_id(), Some(policy.id()));
+        assert_eq!(
+            parsed
+                .to_access_control_entry()
+                .unwrap()
+                .to_bytes()
+                .unwrap(),
+            bytes
+        );
+        assert!(policy.is_referenced_by(sacl.as_acl_ptr()));
+        assert!(!other.is_referenced_by(sacl.as_acl_ptr()));
+        assert!(!policy.is_referenced_by(acl(&[inherit_only]).as_acl_ptr()));
+    }
+}
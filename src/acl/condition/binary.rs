@@ -0,0 +1,500 @@
+use bytes::{read_u16, read_u32, write_u32};
+use claims::ClaimSource;
+
+use super::super::super::SecurityId;
+use super::{
+    BinaryOperator, ConditionalExpression, ConditionalLiteral, IntegerBase, IntegerSign,
+    UnaryOperator, MAX_NESTING_DEPTH,
+};
+
+const SIGNATURE: &[u8] = b"artx";
+
+const PADDING_TOKEN: u8 = 0x00;
+const INT8_TOKEN: u8 = 0x01;
+const INT64_TOKEN: u8 = 0x04;
+const STRING_TOKEN: u8 = 0x10;
+const OCTET_STRING_TOKEN: u8 = 0x18;
+const COMPOSITE_TOKEN: u8 = 0x50;
+const SID_TOKEN: u8 = 0x51;
+const LOCAL_ATTRIBUTE_TOKEN: u8 = 0xF8;
+const USER_ATTRIBUTE_TOKEN: u8 = 0xF9;
+const RESOURCE_ATTRIBUTE_TOKEN: u8 = 0xFA;
+const DEVICE_ATTRIBUTE_TOKEN: u8 = 0xFB;
+
+const SIGN_PLUS: u8 = 0x01;
+const SIGN_MINUS: u8 = 0x02;
+const SIGN_NONE: u8 = 0x03;
+const BASE_OCTAL: u8 = 0x01;
+const BASE_DECIMAL: u8 = 0x02;
+const BASE_HEXADECIMAL: u8 = 0x03;
+
+#[derive(Debug, Fail)]
+pub enum InvalidConditionError {
+    #[fail(display = "Conditional expression does not start with the \"artx\" signature")]
+    MissingSignature,
+
+    #[fail(display = "Conditional expression is truncated")]
+    Truncated,
+
+    #[fail(display = "Unknown conditional expression token: {:#04x}", _0)]
+    UnknownToken(u8),
+
+    #[fail(
+        display = "Invalid conditional expression literal with token {:#04x}",
+        _0
+    )]
+    InvalidLiteral(u8),
+
+    #[fail(
+        display = "Conditional expression operator {:#04x} is missing operands",
+        _0
+    )]
+    MissingOperand(u8),
+
+    #[fail(display = "Conditional expression does not reduce to a single value")]
+    Unbalanced,
+
+    #[fail(display = "Conditional expression nests too deeply")]
+    TooDeep,
+}
+
+impl ConditionalExpression {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InvalidConditionError> {
+        if !bytes.starts_with(SIGNATURE) {
+            return Err(InvalidConditionError::MissingSignature);
+        }
+
+        let mut stack: Vec<(ConditionalExpression, usize)> = Vec::new();
+        let mut offset = SIGNATURE.len();
+
+        while offset < bytes.len() {
+            let token = bytes[offset];
+
+            if token == PADDING_TOKEN {
+                offset += 1;
+            } else if let Some(operator) = UnaryOperator::from_token(token) {
+                let (operand, depth) = stack
+                    .pop()
+                    .ok_or(InvalidConditionError::MissingOperand(token))?;
+
+                stack.push((
+                    ConditionalExpression::unary(operator, operand),
+                    nested_depth(depth)?,
+                ));
+                offset += 1;
+            } else if let Some(operator) = BinaryOperator::from_token(token) {
+                let right = stack.pop();
+                let left = stack.pop();
+
+                match (left, right) {
+                    (Some((left, left_depth)), Some((right, right_depth))) => stack.push((
+                        ConditionalExpression::binary(operator, left, right),
+                        nested_depth(left_depth.max(right_depth))?,
+                    )),
+                    _ => return Err(InvalidConditionError::MissingOperand(token)),
+                }
+
+                offset += 1;
+            } else if let Some(source) = attribute_source(token) {
+                let (name, next_offset) = decode_string(bytes, offset + 1, token)?;
+
+                stack.push((ConditionalExpression::Attribute(source, name), 1));
+                offset = next_offset;
+            } else {
+                let (literal, next_offset) = decode_literal(bytes, offset, 1)?;
+
+                stack.push((ConditionalExpression::Literal(literal), 1));
+                offset = next_offset;
+            }
+        }
+
+        match stack.pop() {
+            Some((expression, _)) if stack.is_empty() => Ok(expression),
+            _ => Err(InvalidConditionError::Unbalanced),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = SIGNATURE.to_vec();
+
+        self.encode(&mut bytes);
+
+        let padded_length = (bytes.len() + 3) & !3;
+
+        bytes.resize(padded_length, PADDING_TOKEN);
+
+        bytes
+    }
+
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        match *self {
+            ConditionalExpression::Literal(ref literal) => encode_literal(literal, buffer),
+            ConditionalExpression::Attribute(source, ref name) => {
+                let token = match source {
+                    ClaimSource::Local => LOCAL_ATTRIBUTE_TOKEN,
+                    ClaimSource::User => USER_ATTRIBUTE_TOKEN,
+                    ClaimSource::Resource => RESOURCE_ATTRIBUTE_TOKEN,
+                    ClaimSource::Device => DEVICE_ATTRIBUTE_TOKEN,
+                };
+
+                buffer.push(token);
+                encode_string(name, buffer);
+            }
+            ConditionalExpression::Unary(operator, ref operand) => {
+                operand.encode(buffer);
+                buffer.push(operator.token());
+            }
+            ConditionalExpression::Binary(operator, ref left, ref right) => {
+                left.encode(buffer);
+                right.encode(buffer);
+                buffer.push(operator.token());
+            }
+        }
+    }
+}
+
+fn attribute_source(token: u8) -> Option<ClaimSource> {
+    match token {
+        LOCAL_ATTRIBUTE_TOKEN => Some(ClaimSource::Local),
+        USER_ATTRIBUTE_TOKEN => Some(ClaimSource::User),
+        RESOURCE_ATTRIBUTE_TOKEN => Some(ClaimSource::Resource),
+        DEVICE_ATTRIBUTE_TOKEN => Some(ClaimSource::Device),
+        _ => None,
+    }
+}
+
+fn nested_depth(depth: usize) -> Result<usize, InvalidConditionError> {
+    if depth >= MAX_NESTING_DEPTH {
+        Err(InvalidConditionError::TooDeep)
+    } else {
+        Ok(depth + 1)
+    }
+}
+
+fn decode_literal(
+    bytes: &[u8],
+    offset: usize,
+    depth: usize,
+) -> Result<(ConditionalLiteral, usize), InvalidConditionError> {
+    let token = bytes[offset];
+    let invalid = InvalidConditionError::InvalidLiteral(token);
+
+    match token {
+        INT8_TOKEN..=INT64_TOKEN => {
+            let low = read_u32(bytes, offset + 1).ok_or(InvalidConditionError::Truncated)?;
+            let high = read_u32(bytes, offset + 5).ok_or(InvalidConditionError::Truncated)?;
+            let sign = match bytes.get(offset + 9) {
+                Some(&SIGN_PLUS) => IntegerSign::Plus,
+                Some(&SIGN_MINUS) => IntegerSign::Minus,
+                Some(&SIGN_NONE) => IntegerSign::None,
+                Some(_) => return Err(invalid),
+                None => return Err(InvalidConditionError::Truncated),
+            };
+            let base = match bytes.get(offset + 10) {
+                Some(&BASE_OCTAL) => IntegerBase::Octal,
+                Some(&BASE_DECIMAL) => IntegerBase::Decimal,
+                Some(&BASE_HEXADECIMAL) => IntegerBase::Hexadecimal,
+                Some(_) => return Err(invalid),
+                None => return Err(InvalidConditionError::Truncated),
+            };
+            let value = (u64::from(high) << 32 | u64::from(low)) as i64;
+
+            Ok((
+                ConditionalLiteral::Integer { value, sign, base },
+                offset + 11,
+            ))
+        }
+        STRING_TOKEN => decode_string(bytes, offset + 1, token)
+            .map(|(string, next_offset)| (ConditionalLiteral::String(string), next_offset)),
+        OCTET_STRING_TOKEN => {
+            let (octets, next_offset) = decode_blob(bytes, offset + 1)?;
+
+            Ok((
+                ConditionalLiteral::OctetString(octets.to_vec()),
+                next_offset,
+            ))
+        }
+        SID_TOKEN => {
+            let (sid_bytes, next_offset) = decode_blob(bytes, offset + 1)?;
+            let sid = SecurityId::from_bytes(sid_bytes).map_err(|_| invalid)?;
+
+            Ok((ConditionalLiteral::Sid(sid), next_offset))
+        }
+        COMPOSITE_TOKEN => {
+            let (contents, next_offset) = decode_blob(bytes, offset + 1)?;
+            let element_depth = nested_depth(depth)?;
+            let mut elements = Vec::new();
+            let mut element_offset = 0;
+
+            while element_offset < contents.len() {
+                let (element, next_element_offset) =
+                    decode_literal(contents, element_offset, element_depth)?;
+
+                elements.push(element);
+                element_offset = next_element_offset;
+            }
+
+            Ok((ConditionalLiteral::Composite(elements), next_offset))
+        }
+        _ => Err(InvalidConditionError::UnknownToken(token)),
+    }
+}
+
+fn decode_blob(bytes: &[u8], offset: usize) -> Result<(&[u8], usize), InvalidConditionError> {
+    let length = read_u32(bytes, offset).ok_or(InvalidConditionError::Truncated)? as usize;
+    let start = offset + 4;
+    let end = start
+        .checked_add(length)
+        .ok_or(InvalidConditionError::Truncated)?;
+    let blob = bytes
+        .get(start..end)
+        .ok_or(InvalidConditionError::Truncated)?;
+
+    Ok((blob, end))
+}
+
+fn decode_string(
+    bytes: &[u8],
+    offset: usize,
+    token: u8,
+) -> Result<(String, usize), InvalidConditionError> {
+    let (string_bytes, next_offset) = decode_blob(bytes, offset)?;
+
+    let unit_bytes = string_bytes.chunks_exact(2);
+
+    if !unit_bytes.remainder().is_empty() {
+        return Err(InvalidConditionError::InvalidLiteral(token));
+    }
+
+    let units: Vec<u16> = unit_bytes.filter_map(|unit| read_u16(unit, 0)).collect();
+    let string =
+        String::from_utf16(&units).map_err(|_| InvalidConditionError::InvalidLiteral(token))?;
+
+    Ok((string, next_offset))
+}
+
+fn encode_literal(literal: &ConditionalLiteral, buffer: &mut Vec<u8>) {
+    match *literal {
+        ConditionalLiteral::Integer { value, sign, base } => {
+            buffer.push(INT64_TOKEN);
+            write_u32(buffer, value as u32);
+            write_u32(buffer, (value >> 32) as u32);
+            buffer.push(match sign {
+                IntegerSign::Plus => SIGN_PLUS,
+                IntegerSign::Minus => SIGN_MINUS,
+                IntegerSign::None => SIGN_NONE,
+            });
+            buffer.push(match base {
+                IntegerBase::Octal => BASE_OCTAL,
+                IntegerBase::Decimal => BASE_DECIMAL,
+                IntegerBase::Hexadecimal => BASE_HEXADECIMAL,
+            });
+        }
+        ConditionalLiteral::String(ref string) => {
+            buffer.push(STRING_TOKEN);
+            encode_string(string, buffer);
+        }
+        ConditionalLiteral::OctetString(ref octets) => {
+            buffer.push(OCTET_STRING_TOKEN);
+            encode_blob(octets, buffer);
+        }
+        ConditionalLiteral::Sid(ref sid) => {
+            buffer.push(SID_TOKEN);
+            encode_blob(sid.as_sid_ptr().as_bytes(), buffer);
+        }
+        ConditionalLiteral::Composite(ref elements) => {
+            let mut contents = Vec::new();
+
+            for element in elements {
+                encode_literal(element, &mut contents);
+            }
+
+            buffer.push(COMPOSITE_TOKEN);
+            encode_blob(&contents, buffer);
+        }
+    }
+}
+
+fn encode_blob(blob: &[u8], buffer: &mut Vec<u8>) {
+    write_u32(buffer, blob.len() as u32);
+    buffer.extend_from_slice(blob);
+}
+
+fn encode_string(string: &str, buffer: &mut Vec<u8>) {
+    let mut string_bytes = Vec::with_capacity(2 * string.len());
+
+    for unit in string.encode_utf16() {
+        string_bytes.extend_from_slice(&[unit as u8, (unit >> 8) as u8]);
+    }
+
+    encode_blob(&string_bytes, buffer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Bytecode laid out by hand from MS-DTYP 2.4.4.17.4, the way Windows encodes conditional ACEs:
+    // UTF-16 names, unsigned decimal integers and zero padding to a four byte boundary.
+    const EXPRESSIONS: &[(&str, &str, &[u8])] = &[
+        (
+            "(@User.Title == \"PM\")",
+            "(@User.Title == \"PM\")",
+            &[
+                b'a', b'r', b't', b'x', //
+                0xF9, 0x0A, 0x00, 0x00, 0x00, b'T', 0, b'i', 0, b't', 0, b'l', 0, b'e', 0, //
+                0x10, 0x04, 0x00, 0x00, 0x00, b'P', 0, b'M', 0, //
+                0x80, 0x00, 0x00, 0x00,
+            ],
+        ),
+        (
+            "(Member_of {SID(BA)})",
+            "(Member_of {SID(S-1-5-32-544)})",
+            &[
+                b'a', b'r', b't', b'x', //
+                0x50, 0x15, 0x00, 0x00, 0x00, //
+                0x51, 0x10, 0x00, 0x00, 0x00, //
+                0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, //
+                0x20, 0x00, 0x00, 0x00, 0x20, 0x02, 0x00, 0x00, //
+                0x89, 0x00,
+            ],
+        ),
+        (
+            "(@User.clearance >= 3 && !(Exists @Resource.secret))",
+            "((@User.clearance >= 3) && (!(Exists @Resource.secret)))",
+            &[
+                b'a', b'r', b't', b'x', //
+                0xF9, 0x12, 0x00, 0x00, 0x00, b'c', 0, b'l', 0, b'e', 0, b'a', 0, b'r', 0, //
+                b'a', 0, b'n', 0, b'c', 0, b'e', 0, //
+                0x04, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x02, //
+                0x85, //
+                0xFA, 0x0C, 0x00, 0x00, 0x00, b's', 0, b'e', 0, b'c', 0, b'r', 0, b'e', 0, //
+                b't', 0, //
+                0x87, 0xA2, 0xA0, 0x00,
+            ],
+        ),
+        (
+            "(@User.dept Any_of {\"HR\", \"IT\"})",
+            "(@User.dept Any_of {\"HR\", \"IT\"})",
+            &[
+                b'a', b'r', b't', b'x', //
+                0xF9, 0x08, 0x00, 0x00, 0x00, b'd', 0, b'e', 0, b'p', 0, b't', 0, //
+                0x50, 0x12, 0x00, 0x00, 0x00, //
+                0x10, 0x04, 0x00, 0x00, 0x00, b'H', 0, b'R', 0, //
+                0x10, 0x04, 0x00, 0x00, 0x00, b'I', 0, b'T', 0, //
+                0x88, 0x00, 0x00, 0x00,
+            ],
+        ),
+    ];
+
+    #[test]
+    fn decodes_windows_bytecode() {
+        for &(_, canonical, bytes) in EXPRESSIONS {
+            let condition = ConditionalExpression::from_bytes(bytes).unwrap();
+
+            assert_eq!(condition.to_string(), canonical);
+            assert_eq!(condition.to_bytes(), bytes);
+        }
+    }
+
+    #[test]
+    fn encodes_parsed_expressions_like_windows() {
+        for &(expression, canonical, bytes) in EXPRESSIONS {
+            let condition: ConditionalExpression = expression.parse().unwrap();
+
+            assert_eq!(condition.to_bytes(), bytes);
+            assert_eq!(
+                canonical.parse::<ConditionalExpression>().unwrap(),
+                condition
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_explicit_plus_signs() {
+        let bytes = [
+            b'a', b'r', b't', b'x', //
+            0xF8, 0x02, 0x00, 0x00, 0x00, b'x', 0, //
+            0x04, 0x2A, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x03, //
+            0x84, 0x00,
+        ];
+        let condition = ConditionalExpression::from_bytes(&bytes).unwrap();
+
+        assert_eq!(condition.to_string(), "(x > +0x2a)");
+        assert_eq!(condition.to_bytes(), &bytes[..]);
+    }
+
+    #[test]
+    fn keeps_integers_outside_the_text_range() {
+        let bytes = [
+            b'a', b'r', b't', b'x', //
+            0xF8, 0x02, 0x00, 0x00, 0x00, b'x', 0, //
+            0x04, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x03, 0x02, //
+            0x84, 0x00,
+        ];
+        let condition = ConditionalExpression::from_bytes(&bytes).unwrap();
+
+        assert_eq!(condition.to_string(), "(x > 18446744073709551615)");
+        assert_eq!(condition.to_bytes(), &bytes[..]);
+        assert!(condition
+            .to_string()
+            .parse::<ConditionalExpression>()
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_bytecode() {
+        let truncated = [b'a', b'r', b't', b'x', 0x10, 0x04, 0x00, 0x00, 0x00, b'P'];
+        let missing_operand = [b'a', b'r', b't', b'x', 0x80, 0x00, 0x00, 0x00];
+        let unbalanced = [
+            b'a', b'r', b't', b'x', 0x10, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        match ConditionalExpression::from_bytes(b"xtra") {
+            Err(InvalidConditionError::MissingSignature) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match ConditionalExpression::from_bytes(&truncated) {
+            Err(InvalidConditionError::Truncated) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match ConditionalExpression::from_bytes(&missing_operand) {
+            Err(InvalidConditionError::MissingOperand(0x80)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match ConditionalExpression::from_bytes(&unbalanced) {
+            Err(InvalidConditionError::Unbalanced) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_deeply_nested_bytecode() {
+        let mut bytes = SIGNATURE.to_vec();
+
+        bytes.push(LOCAL_ATTRIBUTE_TOKEN);
+        encode_string("x", &mut bytes);
+        bytes.extend(vec![UnaryOperator::Not.token(); 65_000]);
+
+        match ConditionalExpression::from_bytes(&bytes) {
+            Err(InvalidConditionError::TooDeep) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_deeply_nested_composites() {
+        let mut literal = ConditionalLiteral::Composite(Vec::new());
+
+        for _ in 0..MAX_NESTING_DEPTH {
+            literal = ConditionalLiteral::Composite(vec![literal]);
+        }
+
+        let bytes = ConditionalExpression::literal(literal).to_bytes();
+
+        match ConditionalExpression::from_bytes(&bytes) {
+            Err(InvalidConditionError::TooDeep) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use std::cmp::Ordering;
+
+use claims::{ClaimValue, Claims};
+
+use super::{BinaryOperator, ConditionalExpression, ConditionalLiteral, UnaryOperator};
+
+enum Operand {
+    Null,
+    Values(Vec<ClaimValue>, bool),
+    Result(Option<bool>),
+}
+
+impl ConditionalExpression {
+    pub fn evaluate(&self, claims: &Claims) -> Option<bool> {
+        match self.operand(claims) {
+            Operand::Null => None,
+            Operand::Values(values, _) => truth_value(&values),
+            Operand::Result(result) => result,
+        }
+    }
+
+    fn operand(&self, claims: &Claims) -> Operand {
+        match *self {
+            ConditionalExpression::Literal(ref literal) => {
+                let mut values = Vec::new();
+
+                collect_values(literal, &mut values);
+
+                Operand::Values(values, false)
+            }
+            ConditionalExpression::Attribute(source, ref name) => {
+                claims.get(source, name).map_or(Operand::Null, |values| {
+                    Operand::Values(values.to_vec(), claims.is_case_sensitive(source, name))
+                })
+            }
+            ConditionalExpression::Unary(operator, ref operand) => {
+                Operand::Result(evaluate_unary(operator, operand, claims))
+            }
+            ConditionalExpression::Binary(operator, ref left, ref right) => {
+                Operand::Result(evaluate_binary(operator, left, right, claims))
+            }
+        }
+    }
+
+    fn values(&self, claims: &Claims) -> Option<Vec<ClaimValue>> {
+        self.values_and_case(claims).map(|(values, _)| values)
+    }
+
+    fn values_and_case(&self, claims: &Claims) -> Option<(Vec<ClaimValue>, bool)> {
+        match self.operand(claims) {
+            Operand::Values(values, case_sensitive) => Some((values, case_sensitive)),
+            Operand::Null | Operand::Result(_) => None,
+        }
+    }
+}
+
+fn evaluate_unary(
+    operator: UnaryOperator,
+    operand: &ConditionalExpression,
+    claims: &Claims,
+) -> Option<bool> {
+    use self::UnaryOperator::*;
+
+    let (groups, requires_all, negated) = match operator {
+        Not => return operand.evaluate(claims).map(|result| !result),
+        Exists | NotExists => {
+            let exists = match *operand {
+                ConditionalExpression::Attribute(source, ref name) => {
+                    claims.get(source, name).is_some()
+                }
+                _ => return None,
+            };
+
+            return Some(exists == (operator == Exists));
+        }
+        MemberOf => (claims.user_groups(), true, false),
+        NotMemberOf => (claims.user_groups(), true, true),
+        MemberOfAny => (claims.user_groups(), false, false),
+        NotMemberOfAny => (claims.user_groups(), false, true),
+        DeviceMemberOf => (claims.device_groups(), true, false),
+        NotDeviceMemberOf => (claims.device_groups(), true, true),
+        DeviceMemberOfAny => (claims.device_groups(), false, false),
+        NotDeviceMemberOfAny => (claims.device_groups(), false, true),
+    };
+
+    let memberships = operand
+        .values(claims)?
+        .iter()
+        .map(|value| match *value {
+            ClaimValue::Sid(ref sid) => Some(groups.is_enabled(sid)),
+            _ => None,
+        })
+        .collect::<Option<Vec<bool>>>()?;
+
+    let is_member = if requires_all {
+        memberships.iter().all(|&is_member| is_member)
+    } else {
+        memberships.iter().any(|&is_member| is_member)
+    };
+
+    Some(is_member != negated)
+}
+
+fn evaluate_binary(
+    operator: BinaryOperator,
+    left: &ConditionalExpression,
+    right: &ConditionalExpression,
+    claims: &Claims,
+) -> Option<bool> {
+    use self::BinaryOperator::*;
+
+    match operator {
+        And => match (left.evaluate(claims), right.evaluate(claims)) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        },
+        Or => match (left.evaluate(claims), right.evaluate(claims)) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        },
+        _ => {
+            let (left, left_case_sensitive) = left.values_and_case(claims)?;
+            let (right, right_case_sensitive) = right.values_and_case(claims)?;
+            let case_sensitive = left_case_sensitive || right_case_sensitive;
+
+            match operator {
+                Equal => are_equal(&left, &right, case_sensitive),
+                NotEqual => are_equal(&left, &right, case_sensitive).map(|result| !result),
+                LessThan => compare(&left, &right, case_sensitive)
+                    .map(|ordering| ordering == Ordering::Less),
+                LessThanOrEqual => compare(&left, &right, case_sensitive)
+                    .map(|ordering| ordering != Ordering::Greater),
+                GreaterThan => compare(&left, &right, case_sensitive)
+                    .map(|ordering| ordering == Ordering::Greater),
+                GreaterThanOrEqual => compare(&left, &right, case_sensitive)
+                    .map(|ordering| ordering != Ordering::Less),
+                Contains => Some(contains_all(&left, &right, case_sensitive)),
+                NotContains => Some(!contains_all(&left, &right, case_sensitive)),
+                AnyOf => Some(contains_any(&right, &left, case_sensitive)),
+                NotAnyOf => Some(!contains_any(&right, &left, case_sensitive)),
+                And | Or => unreachable!(),
+            }
+        }
+    }
+}
+
+fn collect_values(literal: &ConditionalLiteral, values: &mut Vec<ClaimValue>) {
+    match *literal {
+        ConditionalLiteral::Integer { value, .. } => values.push(ClaimValue::Int64(value)),
+        ConditionalLiteral::String(ref string) => values.push(ClaimValue::String(string.clone())),
+        ConditionalLiteral::OctetString(ref octets) => {
+            values.push(ClaimValue::OctetString(octets.clone()))
+        }
+        ConditionalLiteral::Sid(ref sid) => values.push(ClaimValue::Sid(sid.clone())),
+        ConditionalLiteral::Composite(ref elements) => {
+            for element in elements {
+                collect_values(element, values);
+            }
+        }
+    }
+}
+
+fn truth_value(values: &[ClaimValue]) -> Option<bool> {
+    match *values {
+        [ClaimValue::Boolean(value)] => Some(value),
+        [ClaimValue::Int64(value)] => Some(value != 0),
+        [ClaimValue::UInt64(value)] => Some(value != 0),
+        [ClaimValue::String(ref value)] => Some(!value.is_empty()),
+        _ => None,
+    }
+}
+
+fn compare(left: &[ClaimValue], right: &[ClaimValue], case_sensitive: bool) -> Option<Ordering> {
+    match (left, right) {
+        ([left], [right]) => left.compare_values(right, case_sensitive),
+        _ => None,
+    }
+}
+
+fn are_equal(left: &[ClaimValue], right: &[ClaimValue], case_sensitive: bool) -> Option<bool> {
+    match (left, right) {
+        ([left], [right]) => left
+            .compare_values(right, case_sensitive)
+            .map(|ordering| ordering == Ordering::Equal),
+        _ => Some(
+            contains_all(left, right, case_sensitive) && contains_all(right, left, case_sensitive),
+        ),
+    }
+}
+
+fn contains_all(values: &[ClaimValue], expected: &[ClaimValue], case_sensitive: bool) -> bool {
+    expected.iter().all(|expected| {
+        values
+            .iter()
+            .any(|value| is_same(value, expected, case_sensitive))
+    })
+}
+
+fn contains_any(values: &[ClaimValue], candidates: &[ClaimValue], case_sensitive: bool) -> bool {
+    candidates.iter().any(|candidate| {
+        values
+            .iter()
+            .any(|value| is_same(value, candidate, case_sensitive))
+    })
+}
+
+fn is_same(left: &ClaimValue, right: &ClaimValue, case_sensitive: bool) -> bool {
+    left.compare_values(right, case_sensitive) == Some(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::ClaimSource;
+    use {GroupAttributes, SecurityId, TokenGroups, WellKnownSid};
+
+    fn claims() -> Claims {
+        let mut claims = Claims::new();
+        let mut groups = TokenGroups::new();
+
+        claims.insert(ClaimSource::Local, "one", vec![ClaimValue::Int64(1)]);
+        claims.insert(
+            ClaimSource::User,
+            "dept",
+            vec![
+                ClaimValue::String("HR".to_owned()),
+                ClaimValue::String("IT".to_owned()),
+            ],
+        );
+        groups.insert(
+            SecurityId::well_known(WellKnownSid::BuiltinAdministrators).unwrap(),
+            GroupAttributes::ENABLED,
+        );
+        groups.insert(
+            SecurityId::well_known(WellKnownSid::BuiltinUsers).unwrap(),
+            GroupAttributes::USE_FOR_DENY_ONLY,
+        );
+        claims.set_user_groups(groups);
+        claims
+    }
+
+    fn evaluate(expression: &str) -> Option<bool> {
+        expression
+            .parse::<ConditionalExpression>()
+            .unwrap()
+            .evaluate(&claims())
+    }
+
+    #[test]
+    fn combines_unknown_results_with_three_valued_logic() {
+        let operands = [
+            ("(one == 1)", Some(true)),
+            ("(one == 2)", Some(false)),
+            ("(missing == 1)", None),
+        ];
+
+        for &(left, left_result) in &operands {
+            for &(right, right_result) in &operands {
+                let and = match (left_result, right_result) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                };
+                let or = match (left_result, right_result) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                };
+
+                assert_eq!(evaluate(&format!("{} && {}", left, right)), and);
+                assert_eq!(evaluate(&format!("{} || {}", left, right)), or);
+            }
+
+            assert_eq!(
+                evaluate(&format!("!{}", left)),
+                left_result.map(|result| !result)
+            );
+        }
+    }
+
+    #[test]
+    fn treats_missing_attributes_as_unknown() {
+        assert_eq!(evaluate("(@User.missing == \"HR\")"), None);
+        assert_eq!(evaluate("(@User.missing Contains \"HR\")"), None);
+        assert_eq!(evaluate("(@User.missing Any_of {\"HR\"})"), None);
+        assert_eq!(evaluate("(Exists @User.missing)"), Some(false));
+        assert_eq!(evaluate("(Not_Exists @User.missing)"), Some(true));
+        assert_eq!(evaluate("(Exists @User.dept)"), Some(true));
+    }
+
+    #[test]
+    fn matches_sets_with_contains_and_any_of() {
+        assert_eq!(evaluate("(@User.dept Contains \"hr\")"), Some(true));
+        assert_eq!(
+            evaluate("(@User.dept Contains {\"HR\", \"IT\"})"),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate("(@User.dept Contains {\"HR\", \"Sales\"})"),
+            Some(false)
+        );
+        assert_eq!(
+            evaluate("(@User.dept Not_Contains {\"HR\", \"Sales\"})"),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate("(@User.dept Any_of {\"Sales\", \"it\"})"),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate("(@User.dept Any_of {\"Sales\", \"Legal\"})"),
+            Some(false)
+        );
+        assert_eq!(evaluate("(@User.dept Not_Any_of {\"Sales\"})"), Some(true));
+        assert_eq!(evaluate("(@User.dept == {\"IT\", \"HR\"})"), Some(true));
+    }
+
+    #[test]
+    fn checks_enabled_group_memberships() {
+        assert_eq!(evaluate("(Member_of {SID(BA)})"), Some(true));
+        assert_eq!(evaluate("(Member_of {SID(BA), SID(BU)})"), Some(false));
+        assert_eq!(evaluate("(Member_of_Any {SID(BA), SID(BU)})"), Some(true));
+        assert_eq!(evaluate("(Not_Member_of {SID(BU)})"), Some(true));
+        assert_eq!(
+            evaluate("(Not_Member_of_Any {SID(BA), SID(BU)})"),
+            Some(false)
+        );
+        assert_eq!(evaluate("(Device_Member_of {SID(BA)})"), Some(false));
+        assert_eq!(evaluate("(Member_of {\"BA\"})"), None);
+        assert_eq!(evaluate("(Member_of @User.missing)"), None);
+    }
+}
@@ -0,0 +1,448 @@
+mod binary;
+mod evaluate;
+mod parse;
+
+use std::fmt::{self, Display, Formatter};
+
+use claims::ClaimSource;
+
+use super::super::SecurityId;
+use super::ace::AccessControlEntryPtr;
+
+pub use self::binary::InvalidConditionError;
+pub use self::parse::ParseConditionError;
+
+// Expressions are formatted, encoded, evaluated and dropped recursively, so parsing and decoding
+// refuse anything nested deeper than this to keep those walks within the stack.
+const MAX_NESTING_DEPTH: usize = 256;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum IntegerBase {
+    Octal,
+    Decimal,
+    Hexadecimal,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum IntegerSign {
+    Plus,
+    Minus,
+    None,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConditionalLiteral {
+    /// An integer as stored in the bytecode: the 64-bit two's complement `value`, plus the sign
+    /// and base it was written with, which are kept so that it formats and encodes unchanged.
+    Integer {
+        value: i64,
+        sign: IntegerSign,
+        base: IntegerBase,
+    },
+    String(String),
+    OctetString(Vec<u8>),
+    Sid(SecurityId),
+    Composite(Vec<ConditionalLiteral>),
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum UnaryOperator {
+    Not,
+    Exists,
+    NotExists,
+    MemberOf,
+    NotMemberOf,
+    MemberOfAny,
+    NotMemberOfAny,
+    DeviceMemberOf,
+    NotDeviceMemberOf,
+    DeviceMemberOfAny,
+    NotDeviceMemberOfAny,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BinaryOperator {
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    Contains,
+    NotContains,
+    AnyOf,
+    NotAnyOf,
+    And,
+    Or,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConditionalExpression {
+    Literal(ConditionalLiteral),
+    Attribute(ClaimSource, String),
+    Unary(UnaryOperator, Box<ConditionalExpression>),
+    Binary(
+        BinaryOperator,
+        Box<ConditionalExpression>,
+        Box<ConditionalExpression>,
+    ),
+}
+
+const UNARY_OPERATORS: &[(UnaryOperator, u8, &str)] = &[
+    (UnaryOperator::Not, 0xA2, "!"),
+    (UnaryOperator::Exists, 0x87, "Exists"),
+    (UnaryOperator::NotExists, 0x8D, "Not_Exists"),
+    (UnaryOperator::MemberOf, 0x89, "Member_of"),
+    (UnaryOperator::NotMemberOf, 0x90, "Not_Member_of"),
+    (UnaryOperator::MemberOfAny, 0x8B, "Member_of_Any"),
+    (UnaryOperator::NotMemberOfAny, 0x92, "Not_Member_of_Any"),
+    (UnaryOperator::DeviceMemberOf, 0x8A, "Device_Member_of"),
+    (
+        UnaryOperator::NotDeviceMemberOf,
+        0x91,
+        "Not_Device_Member_of",
+    ),
+    (
+        UnaryOperator::DeviceMemberOfAny,
+        0x8C,
+        "Device_Member_of_Any",
+    ),
+    (
+        UnaryOperator::NotDeviceMemberOfAny,
+        0x93,
+        "Not_Device_Member_of_Any",
+    ),
+];
+
+const BINARY_OPERATORS: &[(BinaryOperator, u8, &str)] = &[
+    (BinaryOperator::Equal, 0x80, "=="),
+    (BinaryOperator::NotEqual, 0x81, "!="),
+    (BinaryOperator::LessThan, 0x82, "<"),
+    (BinaryOperator::LessThanOrEqual, 0x83, "<="),
+    (BinaryOperator::GreaterThan, 0x84, ">"),
+    (BinaryOperator::GreaterThanOrEqual, 0x85, ">="),
+    (BinaryOperator::Contains, 0x86, "Contains"),
+    (BinaryOperator::NotContains, 0x8E, "Not_Contains"),
+    (BinaryOperator::AnyOf, 0x88, "Any_of"),
+    (BinaryOperator::NotAnyOf, 0x8F, "Not_Any_of"),
+    (BinaryOperator::And, 0xA0, "&&"),
+    (BinaryOperator::Or, 0xA1, "||"),
+];
+
+impl UnaryOperator {
+    pub fn from_token(token: u8) -> Option<Self> {
+        UNARY_OPERATORS
+            .iter()
+            .find(|&&(_, operator_token, _)| operator_token == token)
+            .map(|&(operator, _, _)| operator)
+    }
+
+    pub fn token(self) -> u8 {
+        self.definition().1
+    }
+
+    pub fn symbol(self) -> &'static str {
+        self.definition().2
+    }
+
+    fn definition(self) -> &'static (UnaryOperator, u8, &'static str) {
+        UNARY_OPERATORS
+            .iter()
+            .find(|&&(operator, _, _)| operator == self)
+            .expect("Missing unary operator definition")
+    }
+}
+
+impl BinaryOperator {
+    pub fn from_token(token: u8) -> Option<Self> {
+        BINARY_OPERATORS
+            .iter()
+            .find(|&&(_, operator_token, _)| operator_token == token)
+            .map(|&(operator, _, _)| operator)
+    }
+
+    pub fn token(self) -> u8 {
+        self.definition().1
+    }
+
+    pub fn symbol(self) -> &'static str {
+        self.definition().2
+    }
+
+    fn definition(self) -> &'static (BinaryOperator, u8, &'static str) {
+        BINARY_OPERATORS
+            .iter()
+            .find(|&&(operator, _, _)| operator == self)
+            .expect("Missing binary operator definition")
+    }
+}
+
+impl ConditionalExpression {
+    pub fn attribute(source: ClaimSource, name: &str) -> Self {
+        ConditionalExpression::Attribute(source, name.to_owned())
+    }
+
+    pub fn literal(literal: ConditionalLiteral) -> Self {
+        ConditionalExpression::Literal(literal)
+    }
+
+    pub fn unary(operator: UnaryOperator, operand: ConditionalExpression) -> Self {
+        ConditionalExpression::Unary(operator, Box::new(operand))
+    }
+
+    pub fn binary(
+        operator: BinaryOperator,
+        left: ConditionalExpression,
+        right: ConditionalExpression,
+    ) -> Self {
+        ConditionalExpression::Binary(operator, Box::new(left), Box::new(right))
+    }
+
+    fn write_operand(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            ConditionalExpression::Literal(ref literal) => Display::fmt(literal, formatter),
+            ConditionalExpression::Attribute(source, ref name) => {
+                write_attribute(formatter, source, name)
+            }
+            ConditionalExpression::Unary(UnaryOperator::Not, ref operand) => {
+                write!(formatter, "(!")?;
+                operand.write_operand(formatter)?;
+                write!(formatter, ")")
+            }
+            ConditionalExpression::Unary(operator, ref operand) => {
+                write!(formatter, "({} ", operator.symbol())?;
+                operand.write_operand(formatter)?;
+                write!(formatter, ")")
+            }
+            ConditionalExpression::Binary(operator, ref left, ref right) => {
+                write!(formatter, "(")?;
+                left.write_operand(formatter)?;
+                write!(formatter, " {} ", operator.symbol())?;
+                right.write_operand(formatter)?;
+                write!(formatter, ")")
+            }
+        }
+    }
+}
+
+impl Display for ConditionalExpression {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            ConditionalExpression::Literal(_) | ConditionalExpression::Attribute(..) => {
+                write!(formatter, "(")?;
+                self.write_operand(formatter)?;
+                write!(formatter, ")")
+            }
+            _ => self.write_operand(formatter),
+        }
+    }
+}
+
+impl Display for ConditionalLiteral {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            ConditionalLiteral::Integer { value, sign, base } => {
+                let (sign, magnitude) = match sign {
+                    IntegerSign::Plus => ("+", value as u64),
+                    IntegerSign::Minus => ("-", value.wrapping_neg() as u64),
+                    IntegerSign::None => ("", value as u64),
+                };
+
+                match base {
+                    IntegerBase::Decimal => write!(formatter, "{}{}", sign, magnitude),
+                    IntegerBase::Hexadecimal => write!(formatter, "{}0x{:x}", sign, magnitude),
+                    IntegerBase::Octal => write!(formatter, "{}0{:o}", sign, magnitude),
+                }
+            }
+            // String literals have no escapes, so one containing a quote cannot be written.
+            ConditionalLiteral::String(ref string) if string.contains('"') => Err(fmt::Error),
+            ConditionalLiteral::String(ref string) => write!(formatter, "\"{}\"", string),
+            ConditionalLiteral::OctetString(ref octets) => {
+                write!(formatter, "#")?;
+
+                for octet in octets {
+                    write!(formatter, "{:02x}", octet)?;
+                }
+
+                Ok(())
+            }
+            ConditionalLiteral::Sid(ref sid) => write!(formatter, "SID({})", sid),
+            ConditionalLiteral::Composite(ref elements) => {
+                write!(formatter, "{{")?;
+
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        write!(formatter, ", ")?;
+                    }
+
+                    Display::fmt(element, formatter)?;
+                }
+
+                write!(formatter, "}}")
+            }
+        }
+    }
+}
+
+fn write_attribute(formatter: &mut Formatter, source: ClaimSource, name: &str) -> fmt::Result {
+    let prefix = match source {
+        ClaimSource::Local => "",
+        ClaimSource::User => "@User.",
+        ClaimSource::Device => "@Device.",
+        ClaimSource::Resource => "@Resource.",
+    };
+
+    write!(formatter, "{}", prefix)?;
+
+    // A local attribute name is written bare, so one that would read as a number or an operator
+    // keyword gets its first character escaped.
+    let escape_first = source == ClaimSource::Local && !is_plain_local_name(name);
+
+    for (index, character) in name.chars().enumerate() {
+        if is_attribute_character(character) && !(index == 0 && escape_first) {
+            write!(formatter, "{}", character)?;
+        } else {
+            write_escaped(formatter, character)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_escaped(formatter: &mut Formatter, character: char) -> fmt::Result {
+    let mut units = [0; 2];
+
+    for unit in character.encode_utf16(&mut units) {
+        write!(formatter, "%{:04x}", unit)?;
+    }
+
+    Ok(())
+}
+
+fn is_plain_local_name(name: &str) -> bool {
+    let word_length = name
+        .find(|character: char| !character.is_ascii_alphanumeric() && character != '_')
+        .unwrap_or(name.len());
+    let word = &name[..word_length];
+    let unary_keywords = UNARY_OPERATORS.iter().map(|&(_, _, symbol)| symbol);
+    let binary_keywords = BINARY_OPERATORS.iter().map(|&(_, _, symbol)| symbol);
+
+    name.starts_with(|character: char| character.is_ascii_alphabetic() || character == '_')
+        && !unary_keywords
+            .chain(binary_keywords)
+            .any(|keyword| keyword.eq_ignore_ascii_case(word))
+}
+
+fn is_attribute_character(character: char) -> bool {
+    character.is_ascii_alphanumeric() || ":./_".contains(character)
+}
+
+impl<'a> AccessControlEntryPtr<'a> {
+    pub fn condition(&self) -> Option<ConditionalExpression> {
+        ConditionalExpression::from_bytes(self.application_data()?).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    fn assert_round_trips(condition: &ConditionalExpression) {
+        let text = condition.to_string();
+        let decoded = ConditionalExpression::from_bytes(&condition.to_bytes()).unwrap();
+
+        assert_eq!(&text.parse::<ConditionalExpression>().unwrap(), condition);
+        assert_eq!(&decoded, condition);
+    }
+
+    fn compare_with(literal: ConditionalLiteral) -> ConditionalExpression {
+        ConditionalExpression::binary(
+            BinaryOperator::Equal,
+            ConditionalExpression::attribute(ClaimSource::User, "x"),
+            ConditionalExpression::literal(literal),
+        )
+    }
+
+    #[test]
+    fn keeps_integer_signs_and_bases() {
+        let integers = [
+            (0, IntegerSign::None, IntegerBase::Octal, "00"),
+            (0, IntegerSign::Minus, IntegerBase::Decimal, "-0"),
+            (7, IntegerSign::Plus, IntegerBase::Decimal, "+7"),
+            (-8, IntegerSign::Minus, IntegerBase::Octal, "-010"),
+            (255, IntegerSign::None, IntegerBase::Hexadecimal, "0xff"),
+            (
+                i64::MAX,
+                IntegerSign::None,
+                IntegerBase::Decimal,
+                "9223372036854775807",
+            ),
+            (
+                i64::MIN,
+                IntegerSign::Minus,
+                IntegerBase::Hexadecimal,
+                "-0x8000000000000000",
+            ),
+        ];
+
+        for &(value, sign, base, text) in &integers {
+            let literal = ConditionalLiteral::Integer { value, sign, base };
+
+            assert_eq!(literal.to_string(), text);
+            assert_round_trips(&compare_with(literal));
+        }
+    }
+
+    #[test]
+    fn writes_string_literals_verbatim() {
+        let literal = ConditionalLiteral::String("100%0022 sure".to_owned());
+
+        assert_eq!(literal.to_string(), "\"100%0022 sure\"");
+        assert_round_trips(&compare_with(literal));
+
+        let quoted = compare_with(ConditionalLiteral::String("say \"hi\"".to_owned()));
+        let mut text = String::new();
+
+        assert!(write!(text, "{}", quoted).is_err());
+        assert_eq!(
+            ConditionalExpression::from_bytes(&quoted.to_bytes()).unwrap(),
+            quoted
+        );
+    }
+
+    #[test]
+    fn escapes_ambiguous_local_attribute_names() {
+        let names = [
+            "Exists",
+            "member_of.group",
+            "Contains",
+            "1st",
+            ".hidden",
+            "_private",
+            "caf\u{e9}",
+            "\u{e9}t\u{e9}",
+        ];
+
+        for name in &names {
+            let attribute = ConditionalExpression::attribute(ClaimSource::Local, name);
+            let comparison = ConditionalExpression::binary(
+                BinaryOperator::Equal,
+                attribute.clone(),
+                ConditionalExpression::literal(ConditionalLiteral::String(name.to_string())),
+            );
+
+            assert_round_trips(&attribute);
+            assert_round_trips(&ConditionalExpression::unary(
+                UnaryOperator::Exists,
+                attribute,
+            ));
+            assert_round_trips(&comparison);
+        }
+
+        assert_eq!(
+            ConditionalExpression::attribute(ClaimSource::Local, "Exists").to_string(),
+            "(%0045xists)"
+        );
+    }
+}
@@ -0,0 +1,667 @@
+use std::str::{self, FromStr};
+
+use claims::ClaimSource;
+
+use super::super::super::{SecurityId, WellKnownSid};
+use super::{
+    is_attribute_character, BinaryOperator, ConditionalExpression, ConditionalLiteral, IntegerBase,
+    IntegerSign, UnaryOperator, MAX_NESTING_DEPTH,
+};
+
+const RELATIONAL_SYMBOLS: &[(&str, BinaryOperator)] = &[
+    ("==", BinaryOperator::Equal),
+    ("!=", BinaryOperator::NotEqual),
+    ("<=", BinaryOperator::LessThanOrEqual),
+    (">=", BinaryOperator::GreaterThanOrEqual),
+    ("<", BinaryOperator::LessThan),
+    (">", BinaryOperator::GreaterThan),
+];
+
+const BINARY_KEYWORDS: &[BinaryOperator] = &[
+    BinaryOperator::Contains,
+    BinaryOperator::NotContains,
+    BinaryOperator::AnyOf,
+    BinaryOperator::NotAnyOf,
+];
+
+const UNARY_KEYWORDS: &[UnaryOperator] = &[
+    UnaryOperator::Exists,
+    UnaryOperator::NotExists,
+    UnaryOperator::MemberOf,
+    UnaryOperator::NotMemberOf,
+    UnaryOperator::MemberOfAny,
+    UnaryOperator::NotMemberOfAny,
+    UnaryOperator::DeviceMemberOf,
+    UnaryOperator::NotDeviceMemberOf,
+    UnaryOperator::DeviceMemberOfAny,
+    UnaryOperator::NotDeviceMemberOfAny,
+];
+
+const ATTRIBUTE_PREFIXES: &[(&str, ClaimSource)] = &[
+    ("@User.", ClaimSource::User),
+    ("@Device.", ClaimSource::Device),
+    ("@Resource.", ClaimSource::Resource),
+];
+
+const SID_ALIASES: &[(&str, WellKnownSid)] = &[
+    ("AN", WellKnownSid::Anonymous),
+    ("AU", WellKnownSid::AuthenticatedUser),
+    ("BA", WellKnownSid::BuiltinAdministrators),
+    ("BG", WellKnownSid::BuiltinGuests),
+    ("BO", WellKnownSid::BuiltinBackupOperators),
+    ("BU", WellKnownSid::BuiltinUsers),
+    ("CG", WellKnownSid::CreatorGroup),
+    ("CO", WellKnownSid::CreatorOwner),
+    ("HI", WellKnownSid::HighLabel),
+    ("IU", WellKnownSid::Interactive),
+    ("LS", WellKnownSid::LocalService),
+    ("LW", WellKnownSid::LowLabel),
+    ("ME", WellKnownSid::MediumLabel),
+    ("NS", WellKnownSid::NetworkService),
+    ("NU", WellKnownSid::Network),
+    ("OW", WellKnownSid::CreatorOwnerRights),
+    ("PS", WellKnownSid::PrincipalSelf),
+    ("PU", WellKnownSid::BuiltinPowerUsers),
+    ("RC", WellKnownSid::RestrictedCode),
+    ("RD", WellKnownSid::BuiltinRemoteDesktopUsers),
+    ("SI", WellKnownSid::SystemLabel),
+    ("SU", WellKnownSid::Service),
+    ("SY", WellKnownSid::LocalSystem),
+    ("WD", WellKnownSid::World),
+    ("WR", WellKnownSid::WriteRestrictedCode),
+];
+
+#[derive(Debug, Fail)]
+pub enum ParseConditionError {
+    #[fail(display = "Unexpected end of conditional expression")]
+    UnexpectedEnd,
+
+    #[fail(
+        display = "Unexpected character {:?} at position {} of conditional expression",
+        character, position
+    )]
+    UnexpectedCharacter { character: char, position: usize },
+
+    #[fail(
+        display = "Invalid literal at position {} of conditional expression: {:?}",
+        position, literal
+    )]
+    InvalidLiteral { position: usize, literal: String },
+
+    #[fail(
+        display = "Conditional expression nests too deeply at position {}",
+        position
+    )]
+    TooDeep { position: usize },
+}
+
+impl FromStr for ConditionalExpression {
+    type Err = ParseConditionError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            input: expression,
+            position: 0,
+            depth: 0,
+        };
+        let (condition, _) = parser.parse_or()?;
+
+        parser.skip_whitespace();
+        parser.expect_end()?;
+
+        Ok(condition)
+    }
+}
+
+// A parsed expression together with the depth of its tree.
+type Node = (ConditionalExpression, usize);
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse_or(&mut self) -> Result<Node, ParseConditionError> {
+        let mut condition = self.parse_and()?;
+
+        loop {
+            self.skip_whitespace();
+
+            let start = self.position;
+
+            if !self.eat("||") {
+                return Ok(condition);
+            }
+
+            let right = self.parse_and()?;
+
+            condition = binary_node(BinaryOperator::Or, condition, right, start)?;
+        }
+    }
+
+    fn parse_and(&mut self) -> Result<Node, ParseConditionError> {
+        let mut condition = self.parse_not()?;
+
+        loop {
+            self.skip_whitespace();
+
+            let start = self.position;
+
+            if !self.eat("&&") {
+                return Ok(condition);
+            }
+
+            let right = self.parse_not()?;
+
+            condition = binary_node(BinaryOperator::And, condition, right, start)?;
+        }
+    }
+
+    fn parse_not(&mut self) -> Result<Node, ParseConditionError> {
+        self.skip_whitespace();
+
+        if self.rest().starts_with('!') && !self.rest().starts_with("!=") {
+            let start = self.position;
+
+            self.position += 1;
+
+            let operand = self.nested(start, Self::parse_not)?;
+
+            return unary_node(UnaryOperator::Not, operand, start);
+        }
+
+        self.parse_term()
+    }
+
+    fn parse_term(&mut self) -> Result<Node, ParseConditionError> {
+        let start = self.position;
+
+        if self.eat("(") {
+            let condition = self.nested(start, Self::parse_or)?;
+
+            self.skip_whitespace();
+            self.expect(')')?;
+
+            return Ok(condition);
+        }
+
+        for &operator in UNARY_KEYWORDS {
+            if self.eat_keyword(operator.symbol()) {
+                let operand = self.parse_operand()?;
+
+                return unary_node(operator, (operand, 1), start);
+            }
+        }
+
+        let left = self.parse_operand()?;
+
+        self.skip_whitespace();
+
+        match self.parse_binary_operator() {
+            Some(operator) => {
+                let right = self.parse_operand()?;
+
+                binary_node(operator, (left, 1), (right, 1), start)
+            }
+            None => Ok((left, 1)),
+        }
+    }
+
+    fn nested<T, F>(&mut self, position: usize, parse: F) -> Result<T, ParseConditionError>
+    where
+        F: FnOnce(&mut Self) -> Result<T, ParseConditionError>,
+    {
+        // Formatting parenthesizes every operator, so leave room for a bracket at each level.
+        if self.depth >= 2 * MAX_NESTING_DEPTH {
+            return Err(ParseConditionError::TooDeep { position });
+        }
+
+        self.depth += 1;
+
+        let result = parse(self);
+
+        self.depth -= 1;
+
+        result
+    }
+
+    fn parse_binary_operator(&mut self) -> Option<BinaryOperator> {
+        for &(symbol, operator) in RELATIONAL_SYMBOLS {
+            if self.eat(symbol) {
+                return Some(operator);
+            }
+        }
+
+        BINARY_KEYWORDS
+            .iter()
+            .cloned()
+            .find(|operator| self.eat_keyword(operator.symbol()))
+    }
+
+    fn parse_operand(&mut self) -> Result<ConditionalExpression, ParseConditionError> {
+        self.skip_whitespace();
+
+        for &(prefix, source) in ATTRIBUTE_PREFIXES {
+            if self.eat_ignoring_case(prefix) {
+                let name = self.parse_attribute_name()?;
+
+                return Ok(ConditionalExpression::Attribute(source, name));
+            }
+        }
+
+        let character = self.peek()?;
+
+        if (character.is_ascii_alphabetic() || character == '_' || character == '%')
+            && !self.rest_starts_with_ignoring_case("SID(")
+        {
+            let name = self.parse_attribute_name()?;
+
+            return Ok(ConditionalExpression::Attribute(ClaimSource::Local, name));
+        }
+
+        self.parse_literal().map(ConditionalExpression::Literal)
+    }
+
+    fn parse_attribute_name(&mut self) -> Result<String, ParseConditionError> {
+        let start = self.position;
+        let mut units = Vec::new();
+
+        loop {
+            match self.rest().chars().next() {
+                Some('%') => {
+                    let escape_start = self.position;
+
+                    match escaped_unit(self.rest()) {
+                        Some(unit) => units.push(unit),
+                        None => return Err(self.invalid_literal(escape_start, 5)),
+                    }
+
+                    self.position += 5;
+                }
+                Some(character) if is_attribute_character(character) => {
+                    units.push(character as u16);
+                    self.position += 1;
+                }
+                _ => break,
+            }
+        }
+
+        if units.is_empty() {
+            return Err(self.unexpected());
+        }
+
+        String::from_utf16(&units).map_err(|_| self.invalid_literal(start, self.position - start))
+    }
+
+    fn parse_literal(&mut self) -> Result<ConditionalLiteral, ParseConditionError> {
+        self.skip_whitespace();
+
+        let start = self.position;
+
+        match self.peek()? {
+            '"' => {
+                self.position += 1;
+
+                let length = self
+                    .rest()
+                    .find('"')
+                    .ok_or(ParseConditionError::UnexpectedEnd)?;
+                let string = self.rest()[..length].to_owned();
+
+                self.position += length + 1;
+
+                Ok(ConditionalLiteral::String(string))
+            }
+            '#' => {
+                self.position += 1;
+
+                let digits = self.take_while(|character| character.is_ascii_hexdigit());
+                let octets = digits
+                    .as_bytes()
+                    .chunks(2)
+                    .map(|pair| {
+                        str::from_utf8(pair)
+                            .ok()
+                            .filter(|pair| pair.len() == 2)
+                            .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    })
+                    .collect::<Option<Vec<u8>>>()
+                    .ok_or_else(|| self.invalid_literal(start, self.position - start))?;
+
+                Ok(ConditionalLiteral::OctetString(octets))
+            }
+            '{' => {
+                self.position += 1;
+
+                let mut elements = Vec::new();
+
+                self.skip_whitespace();
+
+                if !self.eat("}") {
+                    loop {
+                        elements.push(self.nested(start, Self::parse_literal)?);
+                        self.skip_whitespace();
+
+                        if self.eat("}") {
+                            break;
+                        }
+
+                        self.expect(',')?;
+                    }
+                }
+
+                Ok(ConditionalLiteral::Composite(elements))
+            }
+            character if character.is_ascii_digit() || character == '-' || character == '+' => {
+                self.parse_integer()
+            }
+            _ if self.eat_ignoring_case("SID(") => {
+                let length = self
+                    .rest()
+                    .find(')')
+                    .ok_or(ParseConditionError::UnexpectedEnd)?;
+                let sid_string = self.rest()[..length].trim();
+                let sid = parse_sid(sid_string).ok_or_else(|| {
+                    self.invalid_literal(start, self.position + length + 1 - start)
+                })?;
+
+                self.position += length + 1;
+
+                Ok(ConditionalLiteral::Sid(sid))
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn parse_integer(&mut self) -> Result<ConditionalLiteral, ParseConditionError> {
+        let start = self.position;
+        let sign = if self.eat("-") {
+            IntegerSign::Minus
+        } else if self.eat("+") {
+            IntegerSign::Plus
+        } else {
+            IntegerSign::None
+        };
+
+        let (base, radix) = if self.eat_ignoring_case("0x") {
+            (IntegerBase::Hexadecimal, 16)
+        } else if self.rest().starts_with('0')
+            && self.rest()[1..].starts_with(|character: char| character.is_ascii_digit())
+        {
+            self.position += 1;
+            (IntegerBase::Octal, 8)
+        } else {
+            (IntegerBase::Decimal, 10)
+        };
+
+        let digits = self.take_while(|character| character.is_ascii_alphanumeric());
+        let magnitude = u64::from_str_radix(digits, radix)
+            .ok()
+            .filter(|&magnitude| {
+                magnitude <= i64::MAX as u64
+                    || (sign == IntegerSign::Minus && magnitude == i64::MIN as u64)
+            })
+            .ok_or_else(|| self.invalid_literal(start, self.position - start))?;
+        let value = match sign {
+            IntegerSign::Minus => (magnitude as i64).wrapping_neg(),
+            IntegerSign::Plus | IntegerSign::None => magnitude as i64,
+        };
+
+        Ok(ConditionalLiteral::Integer { value, sign, base })
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn peek(&self) -> Result<char, ParseConditionError> {
+        self.rest()
+            .chars()
+            .next()
+            .ok_or(ParseConditionError::UnexpectedEnd)
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn take_while<F: Fn(char) -> bool>(&mut self, predicate: F) -> &'a str {
+        let rest = self.rest();
+        let length = rest
+            .find(|character| !predicate(character))
+            .unwrap_or(rest.len());
+
+        self.position += length;
+
+        &rest[..length]
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        if self.rest().starts_with(expected) {
+            self.position += expected.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_ignoring_case(&mut self, expected: &str) -> bool {
+        if self.rest_starts_with_ignoring_case(expected) {
+            self.position += expected.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let boundary = self
+            .rest()
+            .get(keyword.len()..)
+            .and_then(|after| after.chars().next())
+            .map_or(true, |character| {
+                !character.is_ascii_alphanumeric() && character != '_'
+            });
+
+        boundary && self.eat_ignoring_case(keyword)
+    }
+
+    fn rest_starts_with_ignoring_case(&self, expected: &str) -> bool {
+        self.rest()
+            .get(..expected.len())
+            .map_or(false, |prefix| prefix.eq_ignore_ascii_case(expected))
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseConditionError> {
+        if self.peek()? == expected {
+            self.position += expected.len_utf8();
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), ParseConditionError> {
+        if self.rest().is_empty() {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn unexpected(&self) -> ParseConditionError {
+        match self.rest().chars().next() {
+            Some(character) => ParseConditionError::UnexpectedCharacter {
+                character,
+                position: self.position,
+            },
+            None => ParseConditionError::UnexpectedEnd,
+        }
+    }
+
+    fn invalid_literal(&self, start: usize, length: usize) -> ParseConditionError {
+        let end = (start + length).min(self.input.len());
+
+        ParseConditionError::InvalidLiteral {
+            position: start,
+            literal: self.input.get(start..end).unwrap_or("").to_owned(),
+        }
+    }
+}
+
+fn unary_node(
+    operator: UnaryOperator,
+    (operand, depth): Node,
+    position: usize,
+) -> Result<Node, ParseConditionError> {
+    if depth >= MAX_NESTING_DEPTH {
+        return Err(ParseConditionError::TooDeep { position });
+    }
+
+    Ok((ConditionalExpression::unary(operator, operand), depth + 1))
+}
+
+fn binary_node(
+    operator: BinaryOperator,
+    (left, left_depth): Node,
+    (right, right_depth): Node,
+    position: usize,
+) -> Result<Node, ParseConditionError> {
+    let depth = left_depth.max(right_depth);
+
+    if depth >= MAX_NESTING_DEPTH {
+        return Err(ParseConditionError::TooDeep { position });
+    }
+
+    Ok((
+        ConditionalExpression::binary(operator, left, right),
+        depth + 1,
+    ))
+}
+
+fn escaped_unit(text: &str) -> Option<u16> {
+    let digits = text.strip_prefix('%')?.get(..4)?;
+
+    if digits
+        .chars()
+        .all(|character| character.is_ascii_hexdigit())
+    {
+        u16::from_str_radix(digits, 16).ok()
+    } else {
+        None
+    }
+}
+
+fn parse_sid(sid_string: &str) -> Option<SecurityId> {
+    if let Ok(sid) = sid_string.parse() {
+        return Some(sid);
+    }
+
+    SID_ALIASES
+        .iter()
+        .find(|&&(alias, _)| alias.eq_ignore_ascii_case(sid_string))
+        .and_then(|&(_, well_known_sid)| SecurityId::well_known(well_known_sid).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{ClaimValue, Claims};
+
+    fn parse(expression: &str) -> Result<ConditionalExpression, ParseConditionError> {
+        expression.parse()
+    }
+
+    #[test]
+    fn rejects_deeply_nested_negations() {
+        let expression = format!("{}(@User.x == 1)", "!".repeat(200_000));
+
+        match parse(&expression) {
+            Err(ParseConditionError::TooDeep { .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_long_conjunction_chains() {
+        let expression = format!("{}(x == 1)", "(x == 1) && ".repeat(100_000));
+
+        match parse(&expression) {
+            Err(ParseConditionError::TooDeep { .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_deeply_nested_composites() {
+        let expression = format!("(x Any_of {}{})", "{".repeat(100_000), "}".repeat(100_000));
+
+        match parse(&expression) {
+            Err(ParseConditionError::TooDeep { .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn accepts_expressions_at_the_nesting_limit() {
+        let expression = format!("{}(x == 1)", "!".repeat(MAX_NESTING_DEPTH - 2));
+        let condition = parse(&expression).unwrap();
+        let decoded = ConditionalExpression::from_bytes(&condition.to_bytes()).unwrap();
+
+        let mut claims = Claims::new();
+
+        claims.insert(ClaimSource::Local, "x", vec![ClaimValue::Int64(1)]);
+
+        assert_eq!(decoded, condition);
+        assert_eq!(parse(&condition.to_string()).unwrap(), condition);
+        assert_eq!(condition.evaluate(&claims), Some(true));
+    }
+
+    #[test]
+    fn rejects_integers_outside_the_signed_range() {
+        for &literal in &[
+            "9223372036854775808",
+            "+0x8000000000000000",
+            "-9223372036854775809",
+            "18446744073709551615",
+            "-18446744073709551611",
+        ] {
+            match parse(&format!("(x == {})", literal)) {
+                Err(ParseConditionError::InvalidLiteral { position: 6, .. }) => {}
+                other => panic!("unexpected result for {}: {:?}", literal, other),
+            }
+        }
+
+        for &(literal, expected) in &[
+            ("9223372036854775807", i64::MAX),
+            ("-9223372036854775808", i64::MIN),
+            ("-01000000000000000000000", i64::MIN),
+        ] {
+            match parse(&format!("(x == {})", literal)).unwrap() {
+                ConditionalExpression::Binary(_, _, ref right) => match **right {
+                    ConditionalExpression::Literal(ConditionalLiteral::Integer {
+                        value, ..
+                    }) => {
+                        assert_eq!(value, expected)
+                    }
+                    ref other => panic!("unexpected operand: {:?}", other),
+                },
+                other => panic!("unexpected expression: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn reads_string_literals_verbatim() {
+        let condition = parse("(x == \"100%0022\")").unwrap();
+
+        assert_eq!(condition.to_string(), "(x == \"100%0022\")");
+
+        match parse("(x == \"say \"hi\"\")") {
+            Err(ParseConditionError::UnexpectedCharacter { .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,1080 @@
+use bytes::{read_u16, read_u32, write_u16, write_u32};
+use guid::Guid;
+
+use super::super::{SecurityId, SecurityIdPtr};
+use super::ace::{
+    AccessControlEntryPtr, AccessMask, AceFlags, ACE_HEADER_SIZE,
+    ACE_INHERITED_OBJECT_TYPE_PRESENT, ACE_OBJECT_TYPE_PRESENT,
+};
+use super::condition::ConditionalExpression;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(u8)]
+pub enum AceType {
+    AccessAllowed = 0x00,
+    AccessDenied = 0x01,
+    SystemAudit = 0x02,
+    SystemAlarm = 0x03,
+    AccessAllowedObject = 0x05,
+    AccessDeniedObject = 0x06,
+    SystemAuditObject = 0x07,
+    SystemAlarmObject = 0x08,
+    AccessAllowedCallback = 0x09,
+    AccessDeniedCallback = 0x0A,
+    AccessAllowedCallbackObject = 0x0B,
+    AccessDeniedCallbackObject = 0x0C,
+    SystemAuditCallback = 0x0D,
+    SystemAlarmCallback = 0x0E,
+    SystemAuditCallbackObject = 0x0F,
+    SystemAlarmCallbackObject = 0x10,
+    SystemMandatoryLabel = 0x11,
+    SystemResourceAttribute = 0x12,
+    SystemScopedPolicyId = 0x13,
+    SystemProcessTrustLabel = 0x14,
+}
+
+const ACE_TYPES: &[AceType] = &[
+    AceType::AccessAllowed,
+    AceType::AccessDenied,
+    AceType::SystemAudit,
+    AceType::SystemAlarm,
+    AceType::AccessAllowedObject,
+    AceType::AccessDeniedObject,
+    AceType::SystemAuditObject,
+    AceType::SystemAlarmObject,
+    AceType::AccessAllowedCallback,
+    AceType::AccessDeniedCallback,
+    AceType::AccessAllowedCallbackObject,
+    AceType::AccessDeniedCallbackObject,
+    AceType::SystemAuditCallback,
+    AceType::SystemAlarmCallback,
+    AceType::SystemAuditCallbackObject,
+    AceType::SystemAlarmCallbackObject,
+    AceType::SystemMandatoryLabel,
+    AceType::SystemResourceAttribute,
+    AceType::SystemScopedPolicyId,
+    AceType::SystemProcessTrustLabel,
+];
+
+impl AceType {
+    pub fn from_raw(value: u8) -> Option<Self> {
+        ACE_TYPES
+            .iter()
+            .cloned()
+            .find(|&ace_type| ace_type as u8 == value)
+    }
+
+    pub fn is_object(self) -> bool {
+        use self::AceType::*;
+
+        matches!(
+            self,
+            AccessAllowedObject
+                | AccessDeniedObject
+                | SystemAuditObject
+                | SystemAlarmObject
+                | AccessAllowedCallbackObject
+                | AccessDeniedCallbackObject
+                | SystemAuditCallbackObject
+                | SystemAlarmCallbackObject
+        )
+    }
+
+    pub fn has_application_data(self) -> bool {
+        use self::AceType::*;
+
+        matches!(
+            self,
+            AccessAllowedCallback
+                | AccessDeniedCallback
+                | SystemAuditCallback
+                | SystemAlarmCallback
+                | AccessAllowedCallbackObject
+                | AccessDeniedCallbackObject
+                | SystemAuditCallbackObject
+                | SystemAlarmCallbackObject
+                | SystemResourceAttribute
+        )
+    }
+}
+
+/// An owned access control entry.
+///
+/// Application data is zero-padded to a multiple of four bytes when encoded. Entries built from
+/// the variants directly should be checked with `validate`, since the encoded size must fit in
+/// 16 bits.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AccessControlEntry {
+    AccessAllowed {
+        flags: AceFlags,
+        mask: AccessMask,
+        trustee: SecurityId,
+    },
+    AccessDenied {
+        flags: AceFlags,
+        mask: AccessMask,
+        trustee: SecurityId,
+    },
+    SystemAudit {
+        flags: AceFlags,
+        mask: AccessMask,
+        trustee: SecurityId,
+    },
+    SystemAlarm {
+        flags: AceFlags,
+        mask: AccessMask,
+        trustee: SecurityId,
+    },
+    AccessAllowedObject {
+        flags: AceFlags,
+        mask: AccessMask,
+        object_type: Option<Guid>,
+        inherited_object_type: Option<Guid>,
+        trustee: SecurityId,
+    },
+    AccessDeniedObject {
+        flags: AceFlags,
+        mask: AccessMask,
+        object_type: Option<Guid>,
+        inherited_object_type: Option<Guid>,
+        trustee: SecurityId,
+    },
+    SystemAuditObject {
+        flags: AceFlags,
+        mask: AccessMask,
+        object_type: Option<Guid>,
+        inherited_object_type: Option<Guid>,
+        trustee: SecurityId,
+    },
+    SystemAlarmObject {
+        flags: AceFlags,
+        mask: AccessMask,
+        object_type: Option<Guid>,
+        inherited_object_type: Option<Guid>,
+        trustee: SecurityId,
+    },
+    AccessAllowedCallback {
+        flags: AceFlags,
+        mask: AccessMask,
+        trustee: SecurityId,
+        application_data: Vec<u8>,
+    },
+    AccessDeniedCallback {
+        flags: AceFlags,
+        mask: AccessMask,
+        trustee: SecurityId,
+        application_data: Vec<u8>,
+    },
+    SystemAuditCallback {
+        flags: AceFlags,
+        mask: AccessMask,
+        trustee: SecurityId,
+        application_data: Vec<u8>,
+    },
+    SystemAlarmCallback {
+        flags: AceFlags,
+        mask: AccessMask,
+        trustee: SecurityId,
+        application_data: Vec<u8>,
+    },
+    AccessAllowedCallbackObject {
+        flags: AceFlags,
+        mask: AccessMask,
+        object_type: Option<Guid>,
+        inherited_object_type: Option<Guid>,
+        trustee: SecurityId,
+        application_data: Vec<u8>,
+    },
+    AccessDeniedCallbackObject {
+        flags: AceFlags,
+        mask: AccessMask,
+        object_type: Option<Guid>,
+        inherited_object_type: Option<Guid>,
+        trustee: SecurityId,
+        application_data: Vec<u8>,
+    },
+    SystemAuditCallbackObject {
+        flags: AceFlags,
+        mask: AccessMask,
+        object_type: Option<Guid>,
+        inherited_object_type: Option<Guid>,
+        trustee: SecurityId,
+        application_data: Vec<u8>,
+    },
+    SystemAlarmCallbackObject {
+        flags: AceFlags,
+        mask: AccessMask,
+        object_type: Option<Guid>,
+        inherited_object_type: Option<Guid>,
+        trustee: SecurityId,
+        application_data: Vec<u8>,
+    },
+    SystemMandatoryLabel {
+        flags: AceFlags,
+        mask: AccessMask,
+        label: SecurityId,
+    },
+    SystemResourceAttribute {
+        flags: AceFlags,
+        mask: AccessMask,
+        trustee: SecurityId,
+        attribute_data: Vec<u8>,
+    },
+    SystemScopedPolicyId {
+        flags: AceFlags,
+        mask: AccessMask,
+        policy_id: SecurityId,
+    },
+    SystemProcessTrustLabel {
+        flags: AceFlags,
+        mask: AccessMask,
+        trust_label: SecurityId,
+    },
+    Unknown {
+        ace_type: u8,
+        flags: AceFlags,
+        data: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Fail)]
+#[fail(display = "Invalid access control entry")]
+pub struct InvalidAceError;
+
+#[derive(Debug, Fail)]
+pub enum BuildAceError {
+    #[fail(display = "Access control entry has no trustee")]
+    MissingTrustee,
+
+    #[fail(display = "{:?} access control entries have no object types", _0)]
+    UnexpectedObjectType(AceType),
+
+    #[fail(display = "{:?} access control entries have no application data", _0)]
+    UnexpectedApplicationData(AceType),
+
+    #[fail(display = "{} is not a mandatory integrity label", _0)]
+    NotIntegrityLabel(SecurityId),
+
+    #[fail(
+        display = "Access control entry needs {} bytes, but at most 65535 are allowed",
+        _0
+    )]
+    TooLarge(usize),
+}
+
+struct Parts<'a> {
+    ace_type: u8,
+    flags: AceFlags,
+    mask: Option<AccessMask>,
+    object_types: Option<(Option<Guid>, Option<Guid>)>,
+    trustee: Option<&'a SecurityId>,
+    data: &'a [u8],
+}
+
+impl AccessControlEntry {
+    pub fn builder(ace_type: AceType) -> AccessControlEntryBuilder {
+        AccessControlEntryBuilder::new(ace_type)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InvalidAceError> {
+        let size = read_u16(bytes, 2).ok_or(InvalidAceError)? as usize;
+
+        if size < ACE_HEADER_SIZE || size != bytes.len() {
+            return Err(InvalidAceError);
+        }
+
+        let flags = AceFlags::from_raw(bytes[1]);
+        let body = &bytes[ACE_HEADER_SIZE..];
+        let ace_type = match AceType::from_raw(bytes[0]) {
+            Some(ace_type) => ace_type,
+            None => {
+                return Ok(AccessControlEntry::Unknown {
+                    ace_type: bytes[0],
+                    flags,
+                    data: body.to_vec(),
+                })
+            }
+        };
+
+        let mask = AccessMask::from_raw(read_u32(body, 0).ok_or(InvalidAceError)?);
+        let mut offset = 4;
+        let mut object_type = None;
+        let mut inherited_object_type = None;
+
+        if ace_type.is_object() {
+            let object_flags = read_u32(body, offset).ok_or(InvalidAceError)?;
+
+            offset += 4;
+
+            if object_flags & ACE_OBJECT_TYPE_PRESENT != 0 {
+                object_type = Some(Guid::from_prefix(&body[offset..]).ok_or(InvalidAceError)?);
+                offset += 16;
+            }
+
+            if object_flags & ACE_INHERITED_OBJECT_TYPE_PRESENT != 0 {
+                inherited_object_type =
+                    Some(Guid::from_prefix(&body[offset..]).ok_or(InvalidAceError)?);
+                offset += 16;
+            }
+        }
+
+        let trustee = SecurityIdPtr::from_prefix(body.get(offset..).ok_or(InvalidAceError)?)
+            .ok_or(InvalidAceError)?;
+        let data = body[offset + trustee.as_bytes().len()..].to_vec();
+        let trustee = trustee.to_security_id();
+
+        AccessControlEntryBuilder {
+            ace_type,
+            flags,
+            mask,
+            trustee: Some(trustee),
+            object_type,
+            inherited_object_type,
+            application_data: if ace_type.has_application_data() {
+                data
+            } else {
+                Vec::new()
+            },
+        }
+        .build()
+        .map_err(|_| InvalidAceError)
+    }
+
+    pub fn ace_type(&self) -> Option<AceType> {
+        AceType::from_raw(self.parts().ace_type)
+    }
+
+    pub fn flags(&self) -> AceFlags {
+        self.parts().flags
+    }
+
+    pub fn access_mask(&self) -> Option<AccessMask> {
+        self.parts().mask
+    }
+
+    pub fn trustee<'a>(&'a self) -> Option<SecurityIdPtr<'a>> {
+        self.parts().trustee.map(SecurityId::as_sid_ptr)
+    }
+
+    pub fn object_type(&self) -> Option<Guid> {
+        self.parts()
+            .object_types
+            .and_then(|(object_type, _)| object_type)
+    }
+
+    pub fn inherited_object_type(&self) -> Option<Guid> {
+        self.parts()
+            .object_types
+            .and_then(|(_, inherited_object_type)| inherited_object_type)
+    }
+
+    pub fn application_data(&self) -> &[u8] {
+        match *self {
+            AccessControlEntry::Unknown { .. } => &[],
+            _ => self.parts().data,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        let parts = self.parts();
+        let mut size = ACE_HEADER_SIZE + padded_length(parts.data.len());
+
+        if parts.mask.is_some() {
+            size += 4;
+        }
+
+        if let Some((object_type, inherited_object_type)) = parts.object_types {
+            size += 4;
+            size += 16 * (object_type.iter().count() + inherited_object_type.iter().count());
+        }
+
+        if let Some(trustee) = parts.trustee {
+            size += trustee.as_sid_ptr().as_bytes().len();
+        }
+
+        size
+    }
+
+    pub fn validate(&self) -> Result<(), BuildAceError> {
+        let size = self.size();
+
+        if size > u16::MAX as usize {
+            return Err(BuildAceError::TooLarge(size));
+        }
+
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, BuildAceError> {
+        self.validate()?;
+
+        let mut bytes = Vec::with_capacity(self.size());
+
+        self.encode(&mut bytes);
+
+        Ok(bytes)
+    }
+
+    /// Appends the binary form of the entry, which must have passed `validate`.
+    pub(crate) fn encode(&self, buffer: &mut Vec<u8>) {
+        let parts = self.parts();
+
+        debug_assert!(self.size() <= u16::MAX as usize);
+
+        buffer.push(parts.ace_type);
+        buffer.push(parts.flags.bits());
+        write_u16(buffer, self.size() as u16);
+
+        if let Some(mask) = parts.mask {
+            write_u32(buffer, mask.bits());
+        }
+
+        if let Some((object_type, inherited_object_type)) = parts.object_types {
+            let mut object_flags = 0;
+
+            if object_type.is_some() {
+                object_flags |= ACE_OBJECT_TYPE_PRESENT;
+            }
+
+            if inherited_object_type.is_some() {
+                object_flags |= ACE_INHERITED_OBJECT_TYPE_PRESENT;
+            }
+
+            write_u32(buffer, object_flags);
+
+            for guid in object_type.iter().chain(inherited_object_type.iter()) {
+                buffer.extend_from_slice(guid.as_bytes());
+            }
+        }
+
+        if let Some(trustee) = parts.trustee {
+            buffer.extend_from_slice(trustee.as_sid_ptr().as_bytes());
+        }
+
+        buffer.extend_from_slice(parts.data);
+
+        for _ in parts.data.len()..padded_length(parts.data.len()) {
+            buffer.push(0);
+        }
+    }
+
+    fn parts<'a>(&'a self) -> Parts<'a> {
+        use self::AccessControlEntry::*;
+
+        let ace_type = self.raw_ace_type();
+
+        match *self {
+            AccessAllowed {
+                flags,
+                mask,
+                ref trustee,
+            }
+            | AccessDenied {
+                flags,
+                mask,
+                ref trustee,
+            }
+            | SystemAudit {
+                flags,
+                mask,
+                ref trustee,
+            }
+            | SystemAlarm {
+                flags,
+                mask,
+                ref trustee,
+            }
+            | SystemMandatoryLabel {
+                flags,
+                mask,
+                label: ref trustee,
+            }
+            | SystemScopedPolicyId {
+                flags,
+                mask,
+                policy_id: ref trustee,
+            }
+            | SystemProcessTrustLabel {
+                flags,
+                mask,
+                trust_label: ref trustee,
+            } => Parts {
+                ace_type,
+                flags,
+                mask: Some(mask),
+                object_types: None,
+                trustee: Some(trustee),
+                data: &[],
+            },
+            AccessAllowedObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                ref trustee,
+            }
+            | AccessDeniedObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                ref trustee,
+            }
+            | SystemAuditObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                ref trustee,
+            }
+            | SystemAlarmObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                ref trustee,
+            } => Parts {
+                ace_type,
+                flags,
+                mask: Some(mask),
+                object_types: Some((object_type, inherited_object_type)),
+                trustee: Some(trustee),
+                data: &[],
+            },
+            AccessAllowedCallback {
+                flags,
+                mask,
+                ref trustee,
+                ref application_data,
+            }
+            | AccessDeniedCallback {
+                flags,
+                mask,
+                ref trustee,
+                ref application_data,
+            }
+            | SystemAuditCallback {
+                flags,
+                mask,
+                ref trustee,
+                ref application_data,
+            }
+            | SystemAlarmCallback {
+                flags,
+                mask,
+                ref trustee,
+                ref application_data,
+            }
+            | SystemResourceAttribute {
+                flags,
+                mask,
+                ref trustee,
+                attribute_data: ref application_data,
+            } => Parts {
+                ace_type,
+                flags,
+                mask: Some(mask),
+                object_types: None,
+                trustee: Some(trustee),
+                data: application_data,
+            },
+            AccessAllowedCallbackObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                ref trustee,
+                ref application_data,
+            }
+            | AccessDeniedCallbackObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                ref trustee,
+                ref application_data,
+            }
+            | SystemAuditCallbackObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                ref trustee,
+                ref application_data,
+            }
+            | SystemAlarmCallbackObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                ref trustee,
+                ref application_data,
+            } => Parts {
+                ace_type,
+                flags,
+                mask: Some(mask),
+                object_types: Some((object_type, inherited_object_type)),
+                trustee: Some(trustee),
+                data: application_data,
+            },
+            Unknown {
+                flags, ref data, ..
+            } => Parts {
+                ace_type,
+                flags,
+                mask: None,
+                object_types: None,
+                trustee: None,
+                data,
+            },
+        }
+    }
+
+    fn raw_ace_type(&self) -> u8 {
+        use self::AccessControlEntry::*;
+
+        let ace_type = match *self {
+            AccessAllowed { .. } => AceType::AccessAllowed,
+            AccessDenied { .. } => AceType::AccessDenied,
+            SystemAudit { .. } => AceType::SystemAudit,
+            SystemAlarm { .. } => AceType::SystemAlarm,
+            AccessAllowedObject { .. } => AceType::AccessAllowedObject,
+            AccessDeniedObject { .. } => AceType::AccessDeniedObject,
+            SystemAuditObject { .. } => AceType::SystemAuditObject,
+            SystemAlarmObject { .. } => AceType::SystemAlarmObject,
+            AccessAllowedCallback { .. } => AceType::AccessAllowedCallback,
+            AccessDeniedCallback { .. } => AceType::AccessDeniedCallback,
+            SystemAuditCallback { .. } => AceType::SystemAuditCallback,
+            SystemAlarmCallback { .. } => AceType::SystemAlarmCallback,
+            AccessAllowedCallbackObject { .. } => AceType::AccessAllowedCallbackObject,
+            AccessDeniedCallbackObject { .. } => AceType::AccessDeniedCallbackObject,
+            SystemAuditCallbackObject { .. } => AceType::SystemAuditCallbackObject,
+            SystemAlarmCallbackObject { .. } => AceType::SystemAlarmCallbackObject,
+            SystemMandatoryLabel { .. } => AceType::SystemMandatoryLabel,
+            SystemResourceAttribute { .. } => AceType::SystemResourceAttribute,
+            SystemScopedPolicyId { .. } => AceType::SystemScopedPolicyId,
+            SystemProcessTrustLabel { .. } => AceType::SystemProcessTrustLabel,
+            Unknown { ace_type, .. } => return ace_type,
+        };
+
+        ace_type as u8
+    }
+}
+
+impl<'a> AccessControlEntryPtr<'a> {
+    /// Decodes the entry. Only unrecognised entry types become `Unknown`; a recognised type with
+    /// a malformed body is an error.
+    pub fn to_access_control_entry(self) -> Result<AccessControlEntry, InvalidAceError> {
+        AccessControlEntry::from_bytes(self.as_bytes())
+    }
+}
+
+pub struct AccessControlEntryBuilder {
+    ace_type: AceType,
+    flags: AceFlags,
+    mask: AccessMask,
+    trustee: Option<SecurityId>,
+    object_type: Option<Guid>,
+    inherited_object_type: Option<Guid>,
+    application_data: Vec<u8>,
+}
+
+impl AccessControlEntryBuilder {
+    pub fn new(ace_type: AceType) -> Self {
+        AccessControlEntryBuilder {
+            ace_type,
+            flags: AceFlags::empty(),
+            mask: AccessMask::empty(),
+            trustee: None,
+            object_type: None,
+            inherited_object_type: None,
+            application_data: Vec::new(),
+        }
+    }
+
+    pub fn flags(mut self, flags: AceFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn mask(mut self, mask: AccessMask) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    pub fn trustee<'a, T: Into<SecurityIdPtr<'a>>>(mut self, trustee: T) -> Self {
+        self.trustee = Some(trustee.into().to_security_id());
+        self
+    }
+
+    pub fn object_type(mut self, object_type: Guid) -> Self {
+        self.object_type = Some(object_type);
+        self
+    }
+
+    pub fn inherited_object_type(mut self, inherited_object_type: Guid) -> Self {
+        self.inherited_object_type = Some(inherited_object_type);
+        self
+    }
+
+    pub fn application_data(mut self, application_data: Vec<u8>) -> Self {
+        self.application_data = application_data;
+        self
+    }
+
+    pub fn condition(self, condition: &ConditionalExpression) -> Self {
+        self.application_data(condition.to_bytes())
+    }
+
+    pub fn build(self) -> Result<AccessControlEntry, BuildAceError> {
+        use self::AceType::*;
+
+        let ace_type = self.ace_type;
+
+        if !ace_type.is_object()
+            && (self.object_type.is_some() || self.inherited_object_type.is_some())
+        {
+            return Err(BuildAceError::UnexpectedObjectType(ace_type));
+        }
+
+        if !ace_type.has_application_data() && !self.application_data.is_empty() {
+            return Err(BuildAceError::UnexpectedApplicationData(ace_type));
+        }
+
+        let flags = self.flags;
+        let mask = self.mask;
+        let object_type = self.object_type;
+        let inherited_object_type = self.inherited_object_type;
+        let mut application_data = self.application_data;
+        let trustee = self.trustee.ok_or(BuildAceError::MissingTrustee)?;
+
+        if ace_type == SystemMandatoryLabel && !trustee.is_integrity_label() {
+            return Err(BuildAceError::NotIntegrityLabel(trustee));
+        }
+
+        let padded_data_length = padded_length(application_data.len());
+
+        application_data.resize(padded_data_length, 0);
+
+        let entry = match ace_type {
+            AccessAllowed => AccessControlEntry::AccessAllowed {
+                flags,
+                mask,
+                trustee,
+            },
+            AccessDenied => AccessControlEntry::AccessDenied {
+                flags,
+                mask,
+                trustee,
+            },
+            SystemAudit => AccessControlEntry::SystemAudit {
+                flags,
+                mask,
+                trustee,
+            },
+            SystemAlarm => AccessControlEntry::SystemAlarm {
+                flags,
+                mask,
+                trustee,
+            },
+            AccessAllowedObject => AccessControlEntry::AccessAllowedObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                trustee,
+            },
+            AccessDeniedObject => AccessControlEntry::AccessDeniedObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                trustee,
+            },
+            SystemAuditObject => AccessControlEntry::SystemAuditObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                trustee,
+            },
+            SystemAlarmObject => AccessControlEntry::SystemAlarmObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                trustee,
+            },
+            AccessAllowedCallback => AccessControlEntry::AccessAllowedCallback {
+                flags,
+                mask,
+                trustee,
+                application_data,
+            },
+            AccessDeniedCallback => AccessControlEntry::AccessDeniedCallback {
+                flags,
+                mask,
+                trustee,
+                application_data,
+            },
+            SystemAuditCallback => AccessControlEntry::SystemAuditCallback {
+                flags,
+                mask,
+                trustee,
+                application_data,
+            },
+            SystemAlarmCallback => AccessControlEntry::SystemAlarmCallback {
+                flags,
+                mask,
+                trustee,
+                application_data,
+            },
+            AccessAllowedCallbackObject => AccessControlEntry::AccessAllowedCallbackObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                trustee,
+                application_data,
+            },
+            AccessDeniedCallbackObject => AccessControlEntry::AccessDeniedCallbackObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                trustee,
+                application_data,
+            },
+            SystemAuditCallbackObject => AccessControlEntry::SystemAuditCallbackObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                trustee,
+                application_data,
+            },
+            SystemAlarmCallbackObject => AccessControlEntry::SystemAlarmCallbackObject {
+                flags,
+                mask,
+                object_type,
+                inherited_object_type,
+                trustee,
+                application_data,
+            },
+            SystemMandatoryLabel => AccessControlEntry::SystemMandatoryLabel {
+                flags,
+                mask,
+                label: trustee,
+            },
+            SystemResourceAttribute => AccessControlEntry::SystemResourceAttribute {
+                flags,
+                mask,
+                trustee,
+                attribute_data: application_data,
+            },
+            SystemScopedPolicyId => AccessControlEntry::SystemScopedPolicyId {
+                flags,
+                mask,
+                policy_id: trustee,
+            },
+            SystemProcessTrustLabel => AccessControlEntry::SystemProcessTrustLabel {
+                flags,
+                mask,
+                trust_label: trustee,
+            },
+        };
+
+        entry.validate()?;
+
+        Ok(entry)
+    }
+}
+
+fn padded_length(length: usize) -> usize {
+    (length + 3) & !3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::patch_u32;
+    use WellKnownSid;
+
+    fn local_system() -> SecurityId {
+        SecurityId::well_known(WellKnownSid::LocalSystem).unwrap()
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let entry = AccessControlEntry::builder(AceType::AccessAllowedCallback)
+            .flags(AceFlags::CONTAINER_INHERIT)
+            .mask(AccessMask::GENERIC_READ)
+            .trustee(&local_system())
+            .application_data(b"artx\0".to_vec())
+            .build()
+            .unwrap();
+        let bytes = entry.to_bytes().unwrap();
+
+        assert_eq!(bytes.len(), entry.size());
+        assert_eq!(bytes.len() % 4, 0);
+        assert_eq!(read_u16(&bytes, 2), Some(bytes.len() as u16));
+        assert_eq!(AccessControlEntry::from_bytes(&bytes).unwrap(), entry);
+    }
+
+    #[test]
+    fn pads_unpadded_application_data() {
+        let entry = AccessControlEntry::AccessAllowedCallback {
+            flags: AceFlags::empty(),
+            mask: AccessMask::GENERIC_ALL,
+            trustee: local_system(),
+            application_data: vec![1, 2, 3],
+        };
+        let bytes = entry.to_bytes().unwrap();
+
+        assert_eq!(bytes.len(), 4 + 4 + 12 + 4);
+        assert_eq!(&bytes[bytes.len() - 4..], &[1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn rejects_oversized_entries() {
+        let result = AccessControlEntry::builder(AceType::AccessAllowedCallback)
+            .trustee(&local_system())
+            .application_data(vec![0; 0x1_0000])
+            .build();
+
+        match result {
+            Err(BuildAceError::TooLarge(size)) => assert_eq!(size, 4 + 4 + 12 + 0x1_0000),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let entry = AccessControlEntry::AccessAllowedCallback {
+            flags: AceFlags::empty(),
+            mask: AccessMask::GENERIC_ALL,
+            trustee: local_system(),
+            application_data: vec![0; 0xFFFF],
+        };
+
+        assert!(entry.to_bytes().is_err());
+    }
+
+    #[test]
+    fn rejects_object_types_on_plain_entries() {
+        let result = AccessControlEntry::builder(AceType::AccessAllowed)
+            .trustee(&local_system())
+            .object_type(Guid::from_bytes([1; 16]))
+            .build();
+
+        match result {
+            Err(BuildAceError::UnexpectedObjectType(AceType::AccessAllowed)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn round_trips_object_entries() {
+        let entry = AccessControlEntry::builder(AceType::AccessAllowedObject)
+            .mask(AccessMask::GENERIC_READ)
+            .trustee(&local_system())
+            .object_type(Guid::from_bytes([1; 16]))
+            .inherited_object_type(Guid::from_bytes([2; 16]))
+            .build()
+            .unwrap();
+        let bytes = entry.to_bytes().unwrap();
+
+        assert_eq!(bytes.len(), 4 + 4 + 4 + 16 + 16 + 12);
+        assert_eq!(
+            read_u32(&bytes, 8),
+            Some(ACE_OBJECT_TYPE_PRESENT | ACE_INHERITED_OBJECT_TYPE_PRESENT)
+        );
+        assert_eq!(AccessControlEntry::from_bytes(&bytes).unwrap(), entry);
+    }
+
+    #[test]
+    fn keeps_unknown_entry_types() {
+        let bytes = [0x42, 0x02, 0x08, 0x00, 1, 2, 3, 4];
+        let entry = AccessControlEntry::from_bytes(&bytes).unwrap();
+
+        assert_eq!(
+            entry,
+            AccessControlEntry::Unknown {
+                ace_type: 0x42,
+                flags: AceFlags::CONTAINER_INHERIT,
+                data: vec![1, 2, 3, 4],
+            }
+        );
+        assert_eq!(entry.to_bytes().unwrap(), &bytes[..]);
+    }
+
+    #[test]
+    fn keeps_undefined_mask_and_flag_bits() {
+        let mut bytes = AccessControlEntry::builder(AceType::AccessAllowed)
+            .trustee(&local_system())
+            .build()
+            .unwrap()
+            .to_bytes()
+            .unwrap();
+        let raw_mask = AccessMask::GENERIC_READ.bits() | 0x0080_0000;
+
+        bytes[1] = 0xFF;
+        patch_u32(&mut bytes, 4, raw_mask);
+
+        let entry = AccessControlEntry::from_bytes(&bytes).unwrap();
+        let ptr = AccessControlEntryPtr::from_prefix(&bytes).unwrap();
+
+        match entry {
+            AccessControlEntry::AccessAllowed { flags, mask, .. } => {
+                assert_eq!(flags.bits(), 0xFF);
+                assert_eq!(mask.bits(), raw_mask);
+            }
+            ref other => panic!("unexpected entry: {:?}", other),
+        }
+        assert_eq!(ptr.access_mask().map(|mask| mask.bits()), Some(raw_mask));
+        assert_eq!(ptr.flags().bits(), 0xFF);
+        assert_eq!(entry.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn reports_malformed_entries_of_known_types() {
+        let truncated_label = [0x11, 0x00, 0x08, 0x00, 0x01, 0x00, 0x00, 0x00];
+        let unknown = [0x42, 0x00, 0x08, 0x00, 1, 2, 3, 4];
+
+        match AccessControlEntryPtr::from_prefix(&truncated_label)
+            .unwrap()
+            .to_access_control_entry()
+        {
+            Err(InvalidAceError) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match AccessControlEntryPtr::from_prefix(&unknown)
+            .unwrap()
+            .to_access_control_entry()
+        {
+            Ok(AccessControlEntry::Unknown { ace_type: 0x42, .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_entries() {
+        let bytes = AccessControlEntry::builder(AceType::AccessAllowed)
+            .trustee(&local_system())
+            .build()
+            .unwrap()
+            .to_bytes()
+            .unwrap();
+        let mut oversized = bytes.clone();
+        let mut truncated_trustee = bytes[..16].to_vec();
+        let mut missing_object_type = vec![0x05, 0x00, 0x18, 0x00, 0, 0, 0, 0];
+
+        oversized[2] = 24;
+        truncated_trustee[2] = 16;
+        write_u32(&mut missing_object_type, ACE_OBJECT_TYPE_PRESENT);
+        missing_object_type.extend_from_slice(local_system().as_sid_ptr().as_bytes());
+
+        assert!(AccessControlEntry::from_bytes(&bytes[..3]).is_err());
+        assert!(AccessControlEntry::from_bytes(&[0x00, 0x00, 0x02, 0x00]).is_err());
+        assert!(AccessControlEntry::from_bytes(&oversized).is_err());
+        assert!(AccessControlEntry::from_bytes(&truncated_trustee).is_err());
+        assert!(AccessControlEntry::from_bytes(&missing_object_type).is_err());
+    }
+}
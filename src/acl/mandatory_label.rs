@@ -0,0 +1,99 @@
+use super::super::{IntegrityLevel, SecurityId};
+use super::ace::{AccessMask, AceFlags};
+use super::entry::AccessControlEntry;
+
+bitflags! {
+    pub struct MandatoryPolicy: u32 {
+        const NO_WRITE_UP = 0x1;
+        const NO_READ_UP = 0x2;
+        const NO_EXECUTE_UP = 0x4;
+    }
+}
+
+/// A mandatory integrity label.
+///
+/// The label keeps the raw integrity RID, so labels with levels between the standard ones (for
+/// example, `S-1-16-8448`) are preserved.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MandatoryLabel {
+    rid: u32,
+    policy: MandatoryPolicy,
+}
+
+impl MandatoryLabel {
+    pub fn new(level: IntegrityLevel, policy: MandatoryPolicy) -> Self {
+        MandatoryLabel::from_rid(level.rid(), policy)
+    }
+
+    pub fn from_rid(rid: u32, policy: MandatoryPolicy) -> Self {
+        MandatoryLabel { rid, policy }
+    }
+
+    pub fn rid(&self) -> u32 {
+        self.rid
+    }
+
+    pub fn level(&self) -> Option<IntegrityLevel> {
+        IntegrityLevel::from_rid(self.rid)
+    }
+
+    pub fn policy(&self) -> MandatoryPolicy {
+        self.policy
+    }
+}
+
+impl AccessControlEntry {
+    pub fn mandatory_label(label: MandatoryLabel, flags: AceFlags) -> Self {
+        AccessControlEntry::SystemMandatoryLabel {
+            flags,
+            mask: AccessMask::from_bits_truncate(label.policy.bits()),
+            label: SecurityId::integrity_label_from_rid(label.rid),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::entry::{AceType, BuildAceError};
+    use super::*;
+    use {AccessControlList, WellKnownSid};
+
+    #[test]
+    fn preserves_non_standard_integrity_rids() {
+        let label = MandatoryLabel::from_rid(0x2010, MandatoryPolicy::NO_WRITE_UP);
+        let entry = AccessControlEntry::mandatory_label(label, AceFlags::empty());
+        let mut acl = AccessControlList::new();
+
+        acl.push(&entry).unwrap();
+
+        let parsed = acl.as_acl_ptr().entries().next().unwrap().mandatory_label();
+
+        assert_eq!(parsed, Some(label));
+        assert_eq!(label.level(), None);
+        assert_eq!(
+            entry.trustee().unwrap().to_string(),
+            "S-1-16-8208".to_owned()
+        );
+    }
+
+    #[test]
+    fn maps_standard_levels() {
+        let label = MandatoryLabel::new(IntegrityLevel::High, MandatoryPolicy::NO_READ_UP);
+
+        assert_eq!(label.rid(), 0x3000);
+        assert_eq!(label.level(), Some(IntegrityLevel::High));
+    }
+
+    #[test]
+    fn rejects_trustees_that_are_not_labels() {
+        let world = SecurityId::well_known(WellKnownSid::World).unwrap();
+        let result = AccessControlEntry::builder(AceType::SystemMandatoryLabel)
+            .trustee(&world)
+            .build();
+
+        match result {
+            Err(BuildAceError::NotIntegrityLabel(ref sid)) if *sid == world => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
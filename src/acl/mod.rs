@@ -1,61 +1,119 @@
 mod ace;
-
-use std::borrow::Borrow;
-use std::marker::PhantomData;
-use std::ops::Deref;
-use std::{mem, ptr, slice};
-
-use winapi::shared::minwindef::{DWORD, FALSE};
+mod central_policy;
+mod condition;
+mod entry;
+mod mandatory_label;
+mod resource_attribute;
+mod trust_label;
+
+#[cfg(windows)]
+use std::{ptr, slice};
+
+#[cfg(windows)]
+use winapi::shared::minwindef::DWORD;
+#[cfg(windows)]
 use winapi::shared::winerror::ERROR_SUCCESS;
-use winapi::um::accctrl::PEXPLICIT_ACCESS_W;
+#[cfg(windows)]
 use winapi::um::aclapi::SetEntriesInAclW;
-use winapi::um::errhandlingapi::GetLastError;
-use winapi::um::minwinbase::LPTR;
-use winapi::um::securitybaseapi::InitializeAcl;
-use winapi::um::winbase::{LocalAlloc, LocalFree};
-use winapi::um::winnt::{ACE_HEADER, ACL, ACL_REVISION, PACE_HEADER, PACL};
+#[cfg(windows)]
+use winapi::um::winbase::LocalFree;
+#[cfg(windows)]
+use winapi::um::winnt::{ACL, PACL};
+
+use bytes::{patch_u16, read_u16, write_u16};
 
 pub use self::ace::{
-    AccessControlEntryPtr, AccessInheritance, AccessMask, AccessMode, ExplicitAccess,
+    AccessControlEntryPtr, AccessInheritance, AccessMask, AccessMode, AceFlags, ExplicitAccess,
+};
+pub use self::central_policy::{CentralAccessPolicy, CentralAccessResult, CentralAccessRule};
+pub use self::condition::{
+    BinaryOperator, ConditionalExpression, ConditionalLiteral, IntegerBase, IntegerSign,
+    InvalidConditionError, ParseConditionError, UnaryOperator,
 };
+pub use self::entry::{
+    AccessControlEntry, AccessControlEntryBuilder, AceType, BuildAceError, InvalidAceError,
+};
+pub use self::mandatory_label::{MandatoryLabel, MandatoryPolicy};
+pub use self::resource_attribute::{
+    ClaimAttributeFlags, ClaimSecurityAttribute, InvalidClaimAttributeError,
+};
+
+const ACL_REVISION: u8 = 2;
+const ACL_REVISION_DS: u8 = 4;
+const ACL_HEADER_SIZE: usize = 8;
 
+#[derive(Clone, Copy)]
 pub struct AccessControlListPtr<'a> {
-    acl: PACL,
-    _ptr_lifetime: PhantomData<&'a ()>,
+    bytes: &'a [u8],
 }
 
 impl<'a> AccessControlListPtr<'a> {
-    pub unsafe fn new(acl: PACL) -> Self {
-        AccessControlListPtr {
-            acl,
-            _ptr_lifetime: PhantomData,
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, InvalidAclError> {
+        Self::from_prefix(bytes)
+            .filter(|acl| acl.bytes.len() == bytes.len())
+            .ok_or(InvalidAclError)
+    }
+
+    pub(crate) fn from_prefix(bytes: &'a [u8]) -> Option<Self> {
+        let revision = *bytes.first()?;
+        let size = read_u16(bytes, 2)? as usize;
+        let entry_count = read_u16(bytes, 4)? as usize;
+
+        if revision != ACL_REVISION && revision != ACL_REVISION_DS || size < ACL_HEADER_SIZE {
+            return None;
+        }
+
+        let acl = AccessControlListPtr {
+            bytes: bytes.get(..size)?,
+        };
+
+        let mut remaining_bytes = &acl.bytes[ACL_HEADER_SIZE..];
+
+        for _ in 0..entry_count {
+            let entry = AccessControlEntryPtr::from_prefix(remaining_bytes)?;
+
+            remaining_bytes = &remaining_bytes[entry.size()..];
         }
+
+        Some(acl)
     }
 
-    pub fn num_entries(&self) -> usize {
-        unsafe {
-            let acl = self.acl as *const ACL;
+    /// # Safety
+    ///
+    /// `acl` must point to a valid access control list that outlives the returned value.
+    #[cfg(windows)]
+    pub unsafe fn from_ptr(acl: PACL) -> Self {
+        let size = (*acl).AclSize as usize;
 
-            (*acl).AceCount as usize
+        AccessControlListPtr {
+            bytes: slice::from_raw_parts(acl as *const u8, size),
         }
     }
 
-    pub fn entries<'b>(&'b self) -> AccessControlEntries<'b> {
-        unsafe {
-            let acl_ptr = self.acl as *const ACL;
-            let acl = slice::from_raw_parts(acl_ptr, 2);
-            let first_entry_ptr = acl[2..].as_ptr() as PACE_HEADER;
+    pub fn num_entries(&self) -> usize {
+        read_u16(self.bytes, 4).unwrap_or(0) as usize
+    }
 
-            AccessControlEntries::new(first_entry_ptr, self.num_entries())
+    pub fn entries(&self) -> AccessControlEntries<'a> {
+        AccessControlEntries {
+            remaining_bytes: &self.bytes[ACL_HEADER_SIZE..],
+            remaining: self.num_entries(),
         }
     }
 
-    pub unsafe fn as_ptr(&self) -> PACL {
-        self.acl
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
     }
 
-    pub fn try_to_owned(&self) -> Result<AccessControlList, CreateAclError> {
-        AccessControlList::clone_from(self)
+    #[cfg(windows)]
+    pub fn as_ptr(&self) -> PACL {
+        self.bytes.as_ptr() as *mut ACL
+    }
+
+    pub fn to_access_control_list(self) -> AccessControlList {
+        AccessControlList {
+            bytes: self.bytes.to_vec(),
+        }
     }
 }
 
@@ -66,27 +124,8 @@ impl<'a> AsRef<AccessControlListPtr<'a>> for AccessControlListPtr<'a> {
 }
 
 pub struct AccessControlEntries<'a> {
-    current_entry: *const ACE_HEADER,
+    remaining_bytes: &'a [u8],
     remaining: usize,
-    _ptr_lifetime: PhantomData<&'a ()>,
-}
-
-impl<'a> AccessControlEntries<'a> {
-    pub unsafe fn new(first_entry: PACE_HEADER, count: usize) -> Self {
-        AccessControlEntries {
-            current_entry: first_entry as *const ACE_HEADER,
-            remaining: count,
-            _ptr_lifetime: PhantomData,
-        }
-    }
-
-    unsafe fn advance_entry_ptr(&mut self, byte_count: usize) {
-        let entry_bytes_ptr = self.current_entry as *const u8;
-        let entry_bytes = slice::from_raw_parts(entry_bytes_ptr, byte_count + 1);
-        let next_entry_start = entry_bytes[byte_count..].as_ptr();
-
-        self.current_entry = next_entry_start as *const ACE_HEADER;
-    }
 }
 
 impl<'a> Iterator for AccessControlEntries<'a> {
@@ -97,166 +136,214 @@ impl<'a> Iterator for AccessControlEntries<'a> {
             return None;
         }
 
-        self.remaining -= 0;
+        self.remaining -= 1;
 
-        unsafe {
-            let entry = AccessControlEntryPtr::new(self.current_entry);
-
-            self.advance_entry_ptr(entry.size());
+        let entry = AccessControlEntryPtr::from_prefix(self.remaining_bytes)?;
 
-            Some(entry)
-        }
-    }
-}
-
-pub struct AccessControlListPtrMut<'a> {
-    acl: AccessControlListPtr<'a>,
-}
-
-impl<'a> AccessControlListPtrMut<'a> {
-    pub unsafe fn new(acl: PACL) -> Self {
-        AccessControlListPtrMut {
-            acl: AccessControlListPtr::new(acl),
-        }
-    }
-}
-
-impl<'a> AsRef<AccessControlListPtr<'a>> for AccessControlListPtrMut<'a> {
-    fn as_ref(&self) -> &AccessControlListPtr<'a> {
-        &self.acl
-    }
-}
+        self.remaining_bytes = &self.remaining_bytes[entry.size()..];
 
-impl<'a> Borrow<AccessControlListPtr<'a>> for AccessControlListPtrMut<'a> {
-    fn borrow(&self) -> &AccessControlListPtr<'a> {
-        &self.acl
+        Some(entry)
     }
 }
 
-impl<'a> Deref for AccessControlListPtrMut<'a> {
-    type Target = AccessControlListPtr<'a>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.acl
-    }
-}
+#[derive(Debug, Fail)]
+#[fail(display = "Invalid access control list")]
+pub struct InvalidAclError;
 
+#[cfg(windows)]
 #[derive(Debug, Fail)]
-#[fail(display = "Failed to create empty access control list. Error code: {}", win_error_code)]
+#[fail(display = "Failed to create access control list. Error code: {}", win_error_code)]
 pub struct CreateAclError {
     win_error_code: DWORD,
 }
 
+#[derive(Clone)]
 pub struct AccessControlList {
-    acl: AccessControlListPtrMut<'static>,
+    bytes: Vec<u8>,
 }
 
 impl AccessControlList {
-    pub fn new() -> Result<Self, CreateAclError> {
-        unsafe {
-            let acl_size = mem::size_of::<ACL>();
+    pub fn new() -> Self {
+        let mut bytes = Vec::with_capacity(ACL_HEADER_SIZE);
 
-            let acl_ptr = LocalAlloc(LPTR, acl_size) as PACL;
-            if acl_ptr.is_null() {
-                return Err(CreateAclError {
-                    win_error_code: GetLastError(),
-                });
-            }
-
-            let init_result = InitializeAcl(acl_ptr, acl_size as u32, ACL_REVISION as u32);
-            if init_result == FALSE {
-                return Err(CreateAclError {
-                    win_error_code: GetLastError(),
-                });
-            }
+        bytes.push(ACL_REVISION);
+        bytes.push(0);
+        write_u16(&mut bytes, ACL_HEADER_SIZE as u16);
+        write_u16(&mut bytes, 0);
+        write_u16(&mut bytes, 0);
 
-            let acl = AccessControlListPtrMut::new(acl_ptr);
+        AccessControlList { bytes }
+    }
 
-            Ok(AccessControlList { acl })
-        }
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InvalidAclError> {
+        AccessControlListPtr::from_bytes(bytes).map(AccessControlListPtr::to_access_control_list)
     }
 
-    pub fn clone_from<'a, T>(template_acl: T) -> Result<Self, CreateAclError>
+    pub fn from_entries<'a, I>(entries: I) -> Result<Self, InvalidAclError>
     where
-        T: AsRef<AccessControlListPtr<'a>>,
+        I: IntoIterator<Item = &'a AccessControlEntry>,
     {
-        unsafe { Self::create_acl(template_acl.as_ref().as_ptr(), 0, ptr::null_mut()) }
-    }
+        let mut acl = AccessControlList::new();
 
-    pub fn insert<'trustee>(
-        &mut self,
-        explicit_entry: ExplicitAccess<'trustee>,
-    ) -> Result<(), CreateAclError> {
-        let updated_acl = unsafe { Self::create_acl(self.as_ptr(), 1, explicit_entry.as_ptr())? };
+        for entry in entries {
+            acl.push(entry)?;
+        }
 
-        mem::replace(self, updated_acl);
+        Ok(acl)
+    }
 
-        Ok(())
+    pub fn as_acl_ptr<'a>(&'a self) -> AccessControlListPtr<'a> {
+        AccessControlListPtr { bytes: &self.bytes }
     }
 
-    unsafe fn create_acl(
-        template_acl_ptr: PACL,
-        extra_entries_count: u32,
-        extra_entries_ptr: PEXPLICIT_ACCESS_W,
-    ) -> Result<Self, CreateAclError> {
-        let mut acl_ptr = ptr::null_mut();
-
-        let result = SetEntriesInAclW(
-            extra_entries_count,
-            extra_entries_ptr,
-            template_acl_ptr,
-            &mut acl_ptr,
-        );
-        if result != ERROR_SUCCESS {
-            return Err(CreateAclError {
-                win_error_code: result,
-            });
+    pub fn push(&mut self, entry: &AccessControlEntry) -> Result<(), InvalidAclError> {
+        let size = self.bytes.len() + entry.size();
+        let entry_count = self.as_acl_ptr().num_entries() + 1;
+
+        if size > u16::MAX as usize || entry_count > u16::MAX as usize {
+            return Err(InvalidAclError);
+        }
+
+        if entry.ace_type().map_or(false, AceType::is_object) {
+            self.bytes[0] = ACL_REVISION_DS;
         }
 
-        let acl = AccessControlListPtrMut::new(acl_ptr);
+        entry.encode(&mut self.bytes);
 
-        Ok(AccessControlList { acl })
+        patch_u16(&mut self.bytes, 2, size as u16);
+        patch_u16(&mut self.bytes, 4, entry_count as u16);
+
+        Ok(())
     }
-}
 
-impl Drop for AccessControlList {
-    fn drop(&mut self) {
+    #[cfg(windows)]
+    pub fn insert<'trustee>(
+        &mut self,
+        explicit_entry: ExplicitAccess<'trustee>,
+    ) -> Result<(), CreateAclError> {
+        let mut raw_entry = explicit_entry.to_raw();
+
         unsafe {
-            if !LocalFree(self.acl.as_ptr() as *mut _).is_null() {
+            let mut acl_ptr = ptr::null_mut();
+
+            let result =
+                SetEntriesInAclW(1, &mut raw_entry, self.as_acl_ptr().as_ptr(), &mut acl_ptr);
+            if result != ERROR_SUCCESS {
+                return Err(CreateAclError {
+                    win_error_code: result,
+                });
+            }
+
+            let updated_acl = AccessControlListPtr::from_ptr(acl_ptr).to_access_control_list();
+
+            if !LocalFree(acl_ptr as *mut _).is_null() {
                 panic!("Failed to deallocate access control list");
             }
+
+            *self = updated_acl;
         }
+
+        Ok(())
     }
 }
 
-impl AsRef<AccessControlListPtrMut<'static>> for AccessControlList {
-    fn as_ref(&self) -> &AccessControlListPtrMut<'static> {
-        &self.acl
+impl Default for AccessControlList {
+    fn default() -> Self {
+        AccessControlList::new()
     }
 }
 
-impl AsRef<AccessControlListPtr<'static>> for AccessControlList {
-    fn as_ref(&self) -> &AccessControlListPtr<'static> {
-        self.acl.as_ref()
+impl<'a> From<&'a AccessControlList> for AccessControlListPtr<'a> {
+    fn from(acl: &'a AccessControlList) -> Self {
+        acl.as_acl_ptr()
     }
 }
 
-impl Borrow<AccessControlListPtrMut<'static>> for AccessControlList {
-    fn borrow(&self) -> &AccessControlListPtrMut<'static> {
-        &self.acl
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {Guid, SecurityId, WellKnownSid};
+
+    fn allow(well_known_sid: WellKnownSid, mask: AccessMask) -> AccessControlEntry {
+        AccessControlEntry::builder(AceType::AccessAllowed)
+            .mask(mask)
+            .trustee(&SecurityId::well_known(well_known_sid).unwrap())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn round_trips_entries_through_bytes() {
+        let entries = [
+            allow(WellKnownSid::LocalSystem, AccessMask::GENERIC_ALL),
+            allow(WellKnownSid::World, AccessMask::GENERIC_READ),
+        ];
+        let acl = AccessControlList::from_entries(&entries).unwrap();
+        let bytes = acl.as_acl_ptr().as_bytes();
+        let parsed = AccessControlListPtr::from_bytes(bytes).unwrap();
+
+        assert_eq!(bytes[0], ACL_REVISION);
+        assert_eq!(read_u16(bytes, 2), Some(bytes.len() as u16));
+        assert_eq!(parsed.num_entries(), 2);
+        assert_eq!(
+            parsed
+                .entries()
+                .map(AccessControlEntryPtr::to_access_control_entry)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            entries.to_vec()
+        );
     }
-}
 
-impl Borrow<AccessControlListPtr<'static>> for AccessControlList {
-    fn borrow(&self) -> &AccessControlListPtr<'static> {
-        self.acl.as_ref()
+    #[test]
+    fn uses_the_directory_service_revision_for_object_entries() {
+        let entry = AccessControlEntry::builder(AceType::AccessAllowedObject)
+            .mask(AccessMask::GENERIC_READ)
+            .trustee(&SecurityId::well_known(WellKnownSid::World).unwrap())
+            .object_type(Guid::from_fields(1, 2, 3, [4; 8]))
+            .build()
+            .unwrap();
+        let acl = AccessControlList::from_entries(&[entry]).unwrap();
+
+        assert_eq!(acl.as_acl_ptr().as_bytes()[0], ACL_REVISION_DS);
     }
-}
 
-impl Deref for AccessControlList {
-    type Target = AccessControlListPtrMut<'static>;
+    #[test]
+    fn rejects_malformed_lists() {
+        let acl =
+            AccessControlList::from_entries(&[allow(WellKnownSid::World, AccessMask::GENERIC_ALL)])
+                .unwrap();
+        let bytes = acl.as_acl_ptr().as_bytes();
+        let mut wrong_revision = bytes.to_vec();
+        let mut missing_entry = bytes.to_vec();
+        let mut undersized = bytes.to_vec();
+        let mut trailing = bytes.to_vec();
+
+        wrong_revision[0] = 3;
+        patch_u16(&mut missing_entry, 4, 2);
+        patch_u16(&mut undersized, 2, 4);
+        trailing.push(0);
+
+        assert!(AccessControlListPtr::from_bytes(&wrong_revision).is_err());
+        assert!(AccessControlListPtr::from_bytes(&missing_entry).is_err());
+        assert!(AccessControlListPtr::from_bytes(&undersized).is_err());
+        assert!(AccessControlListPtr::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(AccessControlListPtr::from_bytes(&trailing).is_err());
+        assert!(AccessControlListPtr::from_prefix(&trailing).is_some());
+    }
+
+    #[test]
+    fn rejects_lists_larger_than_the_size_field() {
+        let entry = AccessControlEntry::builder(AceType::AccessAllowedCallback)
+            .trustee(&SecurityId::well_known(WellKnownSid::World).unwrap())
+            .application_data(vec![0; 40_000])
+            .build()
+            .unwrap();
+        let mut acl = AccessControlList::new();
+
+        acl.push(&entry).unwrap();
 
-    fn deref(&self) -> &Self::Target {
-        &self.acl
+        assert!(acl.push(&entry).is_err());
+        assert_eq!(acl.as_acl_ptr().num_entries(), 1);
     }
 }
@@ -0,0 +1,382 @@
+use bytes::{patch_u32, read_u16, read_u32, read_u64, write_u16, write_u32, write_u64};
+use claims::{ClaimSource, ClaimValue, ClaimValueType, Claims};
+
+use super::super::{SecurityId, WellKnownSid};
+use super::ace::{AccessMask, AceFlags};
+use super::entry::AccessControlEntry;
+
+const HEADER_SIZE: usize = 16;
+
+bitflags! {
+    pub struct ClaimAttributeFlags: u32 {
+        const NON_INHERITABLE = 0x0001;
+        const VALUE_CASE_SENSITIVE = 0x0002;
+        const USE_FOR_DENY_ONLY = 0x0004;
+        const DISABLED_BY_DEFAULT = 0x0008;
+        const DISABLED = 0x0010;
+        const MANDATORY = 0x0020;
+    }
+}
+
+impl ClaimAttributeFlags {
+    /// Keeps bits that have no named flag, including the custom range in the high 16 bits.
+    pub fn from_raw(bits: u32) -> Self {
+        unsafe { ClaimAttributeFlags::from_bits_unchecked(bits) }
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum InvalidClaimAttributeError {
+    #[fail(display = "Claim security attribute is truncated")]
+    Truncated,
+
+    #[fail(display = "Unsupported claim value type: {:#06x}", _0)]
+    UnsupportedValueType(u16),
+
+    #[fail(display = "Invalid claim security attribute name")]
+    InvalidName,
+
+    #[fail(display = "Invalid claim security attribute value at index {}", _0)]
+    InvalidValue(usize),
+
+    #[fail(display = "Claim security attribute has no values")]
+    NoValues,
+
+    #[fail(display = "Claim security attribute values have different types")]
+    MixedValueTypes,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClaimSecurityAttribute {
+    name: String,
+    flags: ClaimAttributeFlags,
+    values: Vec<ClaimValue>,
+}
+
+impl ClaimSecurityAttribute {
+    pub fn new(
+        name: &str,
+        flags: ClaimAttributeFlags,
+        values: Vec<ClaimValue>,
+    ) -> Result<Self, InvalidClaimAttributeError> {
+        let value_type = values
+            .first()
+            .ok_or(InvalidClaimAttributeError::NoValues)?
+            .value_type();
+
+        if values.iter().any(|value| value.value_type() != value_type) {
+            return Err(InvalidClaimAttributeError::MixedValueTypes);
+        }
+
+        Ok(ClaimSecurityAttribute {
+            name: name.to_owned(),
+            flags,
+            values,
+        })
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InvalidClaimAttributeError> {
+        let name_offset = read_u32(bytes, 0).ok_or(InvalidClaimAttributeError::Truncated)? as usize;
+        let raw_value_type = read_u16(bytes, 4).ok_or(InvalidClaimAttributeError::Truncated)?;
+        let value_type = ClaimValueType::from_raw(raw_value_type).ok_or(
+            InvalidClaimAttributeError::UnsupportedValueType(raw_value_type),
+        )?;
+        let flags = read_u32(bytes, 8).ok_or(InvalidClaimAttributeError::Truncated)?;
+        let value_count = read_u32(bytes, 12).ok_or(InvalidClaimAttributeError::Truncated)?;
+        let name =
+            read_string(bytes, name_offset).ok_or(InvalidClaimAttributeError::InvalidName)?;
+
+        let values = (0..value_count as usize)
+            .map(|index| {
+                let value_offset = read_u32(bytes, HEADER_SIZE + 4 * index)
+                    .ok_or(InvalidClaimAttributeError::Truncated)?;
+
+                read_value(bytes, value_type, value_offset as usize)
+                    .ok_or(InvalidClaimAttributeError::InvalidValue(index))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        ClaimSecurityAttribute::new(&name, ClaimAttributeFlags::from_raw(flags), values)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn flags(&self) -> ClaimAttributeFlags {
+        self.flags
+    }
+
+    pub fn value_type(&self) -> ClaimValueType {
+        self.values[0].value_type()
+    }
+
+    pub fn values(&self) -> &[ClaimValue] {
+        &self.values
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        write_u32(&mut bytes, 0);
+        write_u16(&mut bytes, self.value_type() as u16);
+        write_u16(&mut bytes, 0);
+        write_u32(&mut bytes, self.flags.bits());
+        write_u32(&mut bytes, self.values.len() as u32);
+
+        for _ in &self.values {
+            write_u32(&mut bytes, 0);
+        }
+
+        let name_offset = bytes.len() as u32;
+
+        patch_u32(&mut bytes, 0, name_offset);
+        write_string(&mut bytes, &self.name);
+
+        for (index, value) in self.values.iter().enumerate() {
+            align(&mut bytes);
+
+            let value_offset = bytes.len() as u32;
+
+            patch_u32(&mut bytes, HEADER_SIZE + 4 * index, value_offset);
+            write_value(&mut bytes, value);
+        }
+
+        align(&mut bytes);
+
+        bytes
+    }
+}
+
+impl AccessControlEntry {
+    pub fn resource_attribute(attribute: &ClaimSecurityAttribute, flags: AceFlags) -> Self {
+        AccessControlEntry::SystemResourceAttribute {
+            flags,
+            mask: AccessMask::empty(),
+            trustee: SecurityId::well_known(WellKnownSid::World)
+                .expect("World security ID is not domain relative"),
+            attribute_data: attribute.to_bytes(),
+        }
+    }
+}
+
+impl Claims {
+    pub fn insert_resource_attribute(&mut self, attribute: &ClaimSecurityAttribute) {
+        let values = attribute.values().to_vec();
+
+        if attribute
+            .flags()
+            .contains(ClaimAttributeFlags::VALUE_CASE_SENSITIVE)
+        {
+            self.insert_case_sensitive(ClaimSource::Resource, attribute.name(), values);
+        } else {
+            self.insert(ClaimSource::Resource, attribute.name(), values);
+        }
+    }
+}
+
+fn read_value(bytes: &[u8], value_type: ClaimValueType, offset: usize) -> Option<ClaimValue> {
+    Some(match value_type {
+        ClaimValueType::Int64 => ClaimValue::Int64(read_u64(bytes, offset)? as i64),
+        ClaimValueType::UInt64 => ClaimValue::UInt64(read_u64(bytes, offset)?),
+        ClaimValueType::Boolean => ClaimValue::Boolean(read_u64(bytes, offset)? != 0),
+        ClaimValueType::String => ClaimValue::String(read_string(bytes, offset)?),
+        ClaimValueType::Fqbn => ClaimValue::Fqbn {
+            version: read_u64(bytes, offset)?,
+            name: read_string(bytes, read_u32(bytes, offset.checked_add(8)?)? as usize)?,
+        },
+        ClaimValueType::Sid => {
+            ClaimValue::Sid(SecurityId::from_bytes(read_octet_string(bytes, offset)?).ok()?)
+        }
+        ClaimValueType::OctetString => {
+            ClaimValue::OctetString(read_octet_string(bytes, offset)?.to_vec())
+        }
+    })
+}
+
+fn write_value(buffer: &mut Vec<u8>, value: &ClaimValue) {
+    match *value {
+        ClaimValue::Int64(value) => write_u64(buffer, value as u64),
+        ClaimValue::UInt64(value) => write_u64(buffer, value),
+        ClaimValue::Boolean(value) => write_u64(buffer, u64::from(value)),
+        ClaimValue::String(ref string) => write_string(buffer, string),
+        ClaimValue::Fqbn { version, ref name } => {
+            let name_offset = buffer.len() + 12;
+
+            write_u64(buffer, version);
+            write_u32(buffer, name_offset as u32);
+            write_string(buffer, name);
+        }
+        ClaimValue::Sid(ref sid) => write_octet_string(buffer, sid.as_sid_ptr().as_bytes()),
+        ClaimValue::OctetString(ref octets) => write_octet_string(buffer, octets),
+    }
+}
+
+fn read_string(bytes: &[u8], offset: usize) -> Option<String> {
+    let mut units = Vec::new();
+
+    loop {
+        let unit = read_u16(bytes, offset.checked_add(2 * units.len())?)?;
+
+        if unit == 0 {
+            return String::from_utf16(&units).ok();
+        }
+
+        units.push(unit);
+    }
+}
+
+fn write_string(buffer: &mut Vec<u8>, string: &str) {
+    for unit in string.encode_utf16() {
+        write_u16(buffer, unit);
+    }
+
+    write_u16(buffer, 0);
+}
+
+fn read_octet_string(bytes: &[u8], offset: usize) -> Option<&[u8]> {
+    let length = read_u32(bytes, offset)? as usize;
+    let start = offset.checked_add(4)?;
+
+    bytes.get(start..start.checked_add(length)?)
+}
+
+fn write_octet_string(buffer: &mut Vec<u8>, octets: &[u8]) {
+    write_u32(buffer, octets.len() as u32);
+    buffer.extend_from_slice(octets);
+}
+
+fn align(buffer: &mut Vec<u8>) {
+    let aligned_length = (buffer.len() + 3) & !3;
+
+    buffer.resize(aligned_length, 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::condition::ConditionalExpression;
+    use super::*;
+    use AccessControlList;
+
+    fn attribute(flags: ClaimAttributeFlags) -> ClaimSecurityAttribute {
+        ClaimSecurityAttribute::new(
+            "Project",
+            flags,
+            vec![
+                ClaimValue::String("Alpha".to_owned()),
+                ClaimValue::String("Beta".to_owned()),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn evaluate(condition: &str, claims: &Claims) -> Option<bool> {
+        condition
+            .parse::<ConditionalExpression>()
+            .unwrap()
+            .evaluate(claims)
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let attribute = attribute(ClaimAttributeFlags::MANDATORY);
+        let bytes = attribute.to_bytes();
+
+        assert_eq!(bytes.len() % 4, 0);
+        assert_eq!(
+            ClaimSecurityAttribute::from_bytes(&bytes).unwrap(),
+            attribute
+        );
+    }
+
+    #[test]
+    fn keeps_custom_flags() {
+        let flags = ClaimAttributeFlags::from_raw(
+            ClaimAttributeFlags::MANDATORY.bits() | 0x0001_0000 | 0x8000_0000,
+        );
+        let attribute = attribute(flags);
+        let bytes = attribute.to_bytes();
+        let parsed = ClaimSecurityAttribute::from_bytes(&bytes).unwrap();
+
+        assert_eq!(read_u32(&bytes, 8), Some(0x8001_0020));
+        assert_eq!(parsed.flags().bits(), 0x8001_0020);
+        assert_eq!(parsed.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn reports_invalid_attribute_data() {
+        let mut acl = AccessControlList::new();
+        let entry = AccessControlEntry::SystemResourceAttribute {
+            flags: AceFlags::empty(),
+            mask: AccessMask::empty(),
+            trustee: SecurityId::well_known(WellKnownSid::World).unwrap(),
+            attribute_data: vec![0; 8],
+        };
+        let valid = AccessControlEntry::resource_attribute(
+            &attribute(ClaimAttributeFlags::empty()),
+            AceFlags::empty(),
+        );
+        let unrelated = AccessControlEntry::builder(super::super::AceType::AccessAllowed)
+            .trustee(&SecurityId::well_known(WellKnownSid::World).unwrap())
+            .build()
+            .unwrap();
+
+        acl.push(&entry).unwrap();
+        acl.push(&valid).unwrap();
+        acl.push(&unrelated).unwrap();
+
+        let mut entries = acl.as_acl_ptr().entries();
+
+        match entries.next().unwrap().resource_attribute() {
+            Err(InvalidClaimAttributeError::UnsupportedValueType(0)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        assert_eq!(
+            entries.next().unwrap().resource_attribute().unwrap(),
+            Some(attribute(ClaimAttributeFlags::empty()))
+        );
+        assert_eq!(entries.next().unwrap().resource_attribute().unwrap(), None);
+    }
+
+    #[test]
+    fn honours_case_sensitive_values() {
+        let mut claims = Claims::new();
+
+        claims.insert_resource_attribute(&attribute(ClaimAttributeFlags::empty()));
+
+        assert_eq!(
+            evaluate("(@Resource.Project Contains \"alpha\")", &claims),
+            Some(true)
+        );
+        assert!(!claims.is_case_sensitive(ClaimSource::Resource, "project"));
+
+        claims.insert_resource_attribute(&attribute(ClaimAttributeFlags::VALUE_CASE_SENSITIVE));
+
+        assert!(claims.is_case_sensitive(ClaimSource::Resource, "project"));
+        assert_eq!(
+            evaluate("(@Resource.Project Contains \"alpha\")", &claims),
+            Some(false)
+        );
+        assert_eq!(
+            evaluate("(@Resource.Project Contains \"Alpha\")", &claims),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate("(@Resource.Project Any_of {\"beta\", \"gamma\"})", &claims),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn compares_strings_with_the_requested_case() {
+        let upper = ClaimValue::String("ABC".to_owned());
+        let lower = ClaimValue::String("abc".to_owned());
+
+        assert_eq!(upper.compare(&lower), Some(::std::cmp::Ordering::Equal));
+        assert_eq!(
+            upper.compare_case_sensitive(&lower),
+            Some(::std::cmp::Ordering::Less)
+        );
+    }
+}
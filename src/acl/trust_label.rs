@@ -0,0 +1,119 @@
+use super::super::{SecurityId, TrustLabel};
+use super::ace::{AccessMask, AceFlags};
+use super::entry::AccessControlEntry;
+use super::AccessControlListPtr;
+
+impl AccessControlEntry {
+    pub fn trust_label(label: TrustLabel, mask: AccessMask, flags: AceFlags) -> Self {
+        AccessControlEntry::SystemProcessTrustLabel {
+            flags,
+            mask,
+            trust_label: SecurityId::trust_label(label),
+        }
+    }
+}
+
+impl<'a> AccessControlListPtr<'a> {
+    pub fn trust_label_restriction(&self, caller: TrustLabel) -> Option<AccessMask> {
+        self.entries()
+            .filter(|entry| !entry.flags().contains(AceFlags::INHERIT_ONLY))
+            .filter_map(|entry| Some((entry.trust_label()?, entry.access_mask()?)))
+            .filter(|&(label, _)| !caller.dominates(&label))
+            .map(|(_, mask)| mask)
+            .fold(None, |restriction, mask| {
+                Some(restriction.map_or(mask, |restriction| restriction & mask))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::AccessControlList;
+    use super::*;
+    use {ProtectionLevel, ProtectionType};
+
+    fn label(protection_type: ProtectionType, protection_level: ProtectionLevel) -> TrustLabel {
+        TrustLabel::new(protection_type, protection_level)
+    }
+
+    #[test]
+    fn round_trips_trust_label_entries() {
+        let antimalware = label(ProtectionType::Lite, ProtectionLevel::Antimalware);
+        let entry = AccessControlEntry::trust_label(
+            antimalware,
+            AccessMask::GENERIC_READ,
+            AceFlags::CONTAINER_INHERIT,
+        );
+        let bytes = entry.to_bytes().unwrap();
+
+        assert_eq!(
+            bytes,
+            vec![
+                0x14, 0x02, 0x18, 0x00, //
+                0x00, 0x00, 0x00, 0x80, //
+                0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x13, //
+                0x00, 0x02, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00,
+            ]
+        );
+        assert_eq!(AccessControlEntry::from_bytes(&bytes).unwrap(), entry);
+
+        let mut acl = AccessControlList::new();
+
+        acl.push(&entry).unwrap();
+
+        let parsed = acl.as_acl_ptr().entries().next().unwrap();
+
+        assert_eq!(parsed.as_bytes(), &bytes[..]);
+        assert_eq!(parsed.trust_label(), Some(antimalware));
+        assert_eq!(parsed.access_mask(), Some(AccessMask::GENERIC_READ));
+        assert_eq!(parsed.flags(), AceFlags::CONTAINER_INHERIT);
+        assert_eq!(parsed.to_access_control_entry().unwrap(), entry);
+    }
+
+    #[test]
+    fn restricts_callers_that_do_not_dominate_the_label() {
+        let read = AccessMask::GENERIC_READ | AccessMask::SYNCHRONIZE;
+        let acl = AccessControlList::from_entries(&[
+            AccessControlEntry::trust_label(
+                label(ProtectionType::Lite, ProtectionLevel::WinTcb),
+                read,
+                AceFlags::empty(),
+            ),
+            AccessControlEntry::trust_label(
+                label(ProtectionType::Lite, ProtectionLevel::Antimalware),
+                AccessMask::GENERIC_READ | AccessMask::GENERIC_WRITE,
+                AceFlags::empty(),
+            ),
+            AccessControlEntry::trust_label(
+                label(ProtectionType::Full, ProtectionLevel::WinTcb),
+                AccessMask::empty(),
+                AceFlags::INHERIT_ONLY | AceFlags::CONTAINER_INHERIT,
+            ),
+        ])
+        .unwrap();
+        let acl = acl.as_acl_ptr();
+
+        assert_eq!(
+            acl.trust_label_restriction(label(ProtectionType::Full, ProtectionLevel::WinTcb)),
+            None
+        );
+        assert_eq!(
+            acl.trust_label_restriction(label(ProtectionType::Lite, ProtectionLevel::Windows)),
+            Some(read)
+        );
+        assert_eq!(
+            acl.trust_label_restriction(label(ProtectionType::None, ProtectionLevel::None)),
+            Some(AccessMask::GENERIC_READ)
+        );
+        assert_eq!(
+            acl.trust_label_restriction(label(ProtectionType::Full, ProtectionLevel::Authenticode)),
+            Some(AccessMask::GENERIC_READ)
+        );
+        assert_eq!(
+            AccessControlList::new()
+                .as_acl_ptr()
+                .trust_label_restriction(label(ProtectionType::None, ProtectionLevel::None)),
+            None
+        );
+    }
+}
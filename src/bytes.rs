@@ -0,0 +1,53 @@
+pub fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let value_bytes = bytes.get(offset..offset.checked_add(2)?)?;
+
+    Some(u16::from(value_bytes[0]) | u16::from(value_bytes[1]) << 8)
+}
+
+pub fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let value_bytes = bytes.get(offset..offset.checked_add(4)?)?;
+
+    Some(
+        u32::from(value_bytes[0])
+            | u32::from(value_bytes[1]) << 8
+            | u32::from(value_bytes[2]) << 16
+            | u32::from(value_bytes[3]) << 24,
+    )
+}
+
+pub fn read_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    let low = read_u32(bytes, offset)?;
+    let high = read_u32(bytes, offset.checked_add(4)?)?;
+
+    Some(u64::from(high) << 32 | u64::from(low))
+}
+
+pub fn write_u16(buffer: &mut Vec<u8>, value: u16) {
+    buffer.extend_from_slice(&[value as u8, (value >> 8) as u8]);
+}
+
+pub fn write_u32(buffer: &mut Vec<u8>, value: u32) {
+    buffer.extend_from_slice(&[
+        value as u8,
+        (value >> 8) as u8,
+        (value >> 16) as u8,
+        (value >> 24) as u8,
+    ]);
+}
+
+pub fn write_u64(buffer: &mut Vec<u8>, value: u64) {
+    write_u32(buffer, value as u32);
+    write_u32(buffer, (value >> 32) as u32);
+}
+
+pub fn patch_u16(buffer: &mut [u8], offset: usize, value: u16) {
+    buffer[offset] = value as u8;
+    buffer[offset + 1] = (value >> 8) as u8;
+}
+
+pub fn patch_u32(buffer: &mut [u8], offset: usize, value: u32) {
+    buffer[offset] = value as u8;
+    buffer[offset + 1] = (value >> 8) as u8;
+    buffer[offset + 2] = (value >> 16) as u8;
+    buffer[offset + 3] = (value >> 24) as u8;
+}
@@ -0,0 +1,184 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use {SecurityId, TokenGroups};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ClaimSource {
+    Local,
+    User,
+    Device,
+    Resource,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(u16)]
+pub enum ClaimValueType {
+    Int64 = 0x0001,
+    UInt64 = 0x0002,
+    String = 0x0003,
+    Fqbn = 0x0004,
+    Sid = 0x0005,
+    Boolean = 0x0006,
+    OctetString = 0x0010,
+}
+
+impl ClaimValueType {
+    pub fn from_raw(value: u16) -> Option<Self> {
+        match value {
+            0x0001 => Some(ClaimValueType::Int64),
+            0x0002 => Some(ClaimValueType::UInt64),
+            0x0003 => Some(ClaimValueType::String),
+            0x0004 => Some(ClaimValueType::Fqbn),
+            0x0005 => Some(ClaimValueType::Sid),
+            0x0006 => Some(ClaimValueType::Boolean),
+            0x0010 => Some(ClaimValueType::OctetString),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ClaimValue {
+    Int64(i64),
+    UInt64(u64),
+    String(String),
+    Fqbn { version: u64, name: String },
+    Sid(SecurityId),
+    Boolean(bool),
+    OctetString(Vec<u8>),
+}
+
+impl ClaimValue {
+    pub fn value_type(&self) -> ClaimValueType {
+        match *self {
+            ClaimValue::Int64(_) => ClaimValueType::Int64,
+            ClaimValue::UInt64(_) => ClaimValueType::UInt64,
+            ClaimValue::String(_) => ClaimValueType::String,
+            ClaimValue::Fqbn { .. } => ClaimValueType::Fqbn,
+            ClaimValue::Sid(_) => ClaimValueType::Sid,
+            ClaimValue::Boolean(_) => ClaimValueType::Boolean,
+            ClaimValue::OctetString(_) => ClaimValueType::OctetString,
+        }
+    }
+
+    /// Compares two values the way conditional expressions do, ignoring the case of strings.
+    pub fn compare(&self, other: &ClaimValue) -> Option<Ordering> {
+        self.compare_values(other, false)
+    }
+
+    pub fn compare_case_sensitive(&self, other: &ClaimValue) -> Option<Ordering> {
+        self.compare_values(other, true)
+    }
+
+    pub(crate) fn compare_values(
+        &self,
+        other: &ClaimValue,
+        case_sensitive: bool,
+    ) -> Option<Ordering> {
+        use self::ClaimValue::*;
+
+        let compare_strings = |left: &str, right: &str| {
+            if case_sensitive {
+                left.cmp(right)
+            } else {
+                left.to_lowercase().cmp(&right.to_lowercase())
+            }
+        };
+
+        match (self, other) {
+            (String(left), String(right)) => Some(compare_strings(left, right)),
+            (
+                Fqbn {
+                    version: left_version,
+                    name: left_name,
+                },
+                Fqbn {
+                    version: right_version,
+                    name: right_name,
+                },
+            ) if compare_strings(left_name, right_name) == Ordering::Equal => {
+                Some(left_version.cmp(right_version))
+            }
+            (Sid(left), Sid(right)) => Some(left.cmp(right)),
+            (OctetString(left), OctetString(right)) => Some(left.cmp(right)),
+            (left, right) => Some(left.as_integer()?.cmp(&right.as_integer()?)),
+        }
+    }
+
+    fn as_integer(&self) -> Option<i128> {
+        match *self {
+            ClaimValue::Int64(value) => Some(i128::from(value)),
+            ClaimValue::UInt64(value) => Some(i128::from(value)),
+            ClaimValue::Boolean(value) => Some(i128::from(value)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Claims {
+    attributes: HashMap<(ClaimSource, String), Vec<ClaimValue>>,
+    case_sensitive: HashSet<(ClaimSource, String)>,
+    user_groups: TokenGroups,
+    device_groups: TokenGroups,
+}
+
+impl Claims {
+    pub fn new() -> Self {
+        Claims::default()
+    }
+
+    pub fn insert(&mut self, source: ClaimSource, name: &str, values: Vec<ClaimValue>) {
+        let key = (source, name.to_lowercase());
+
+        self.case_sensitive.remove(&key);
+        self.attributes.insert(key, values);
+    }
+
+    /// Inserts an attribute whose string values are compared case-sensitively.
+    pub fn insert_case_sensitive(
+        &mut self,
+        source: ClaimSource,
+        name: &str,
+        values: Vec<ClaimValue>,
+    ) {
+        let key = (source, name.to_lowercase());
+
+        self.case_sensitive.insert(key.clone());
+        self.attributes.insert(key, values);
+    }
+
+    pub fn remove(&mut self, source: ClaimSource, name: &str) -> Option<Vec<ClaimValue>> {
+        let key = (source, name.to_lowercase());
+
+        self.case_sensitive.remove(&key);
+        self.attributes.remove(&key)
+    }
+
+    pub fn is_case_sensitive(&self, source: ClaimSource, name: &str) -> bool {
+        self.case_sensitive.contains(&(source, name.to_lowercase()))
+    }
+
+    pub fn get(&self, source: ClaimSource, name: &str) -> Option<&[ClaimValue]> {
+        self.attributes
+            .get(&(source, name.to_lowercase()))
+            .map(Vec::as_slice)
+    }
+
+    pub fn user_groups(&self) -> &TokenGroups {
+        &self.user_groups
+    }
+
+    pub fn set_user_groups(&mut self, groups: TokenGroups) {
+        self.user_groups = groups;
+    }
+
+    pub fn device_groups(&self) -> &TokenGroups {
+        &self.device_groups
+    }
+
+    pub fn set_device_groups(&mut self, groups: TokenGroups) {
+        self.device_groups = groups;
+    }
+}
@@ -0,0 +1,150 @@
+use std::fmt::{self, Debug, Display, Formatter};
+use std::str::FromStr;
+
+use bytes::{read_u16, read_u32};
+
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Guid([u8; 16]);
+
+impl Guid {
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Guid(bytes)
+    }
+
+    pub fn from_fields(data1: u32, data2: u16, data3: u16, data4: [u8; 8]) -> Self {
+        let mut bytes = [0; 16];
+
+        bytes[..4].copy_from_slice(&data1.to_le_bytes());
+        bytes[4..6].copy_from_slice(&data2.to_le_bytes());
+        bytes[6..8].copy_from_slice(&data3.to_le_bytes());
+        bytes[8..].copy_from_slice(&data4);
+
+        Guid(bytes)
+    }
+
+    pub(crate) fn from_prefix(bytes: &[u8]) -> Option<Self> {
+        let mut guid = [0; 16];
+
+        guid.copy_from_slice(bytes.get(..16)?);
+
+        Some(Guid(guid))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+}
+
+impl Display for Guid {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        let bytes = &self.0;
+
+        write!(
+            formatter,
+            "{:08x}-{:04x}-{:04x}-{:02x}{:02x}-",
+            read_u32(bytes, 0).unwrap_or(0),
+            read_u16(bytes, 4).unwrap_or(0),
+            read_u16(bytes, 6).unwrap_or(0),
+            bytes[8],
+            bytes[9]
+        )?;
+
+        for byte in &bytes[10..] {
+            write!(formatter, "{:02x}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Debug for Guid {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        Display::fmt(self, formatter)
+    }
+}
+
+#[derive(Debug, Fail)]
+#[fail(display = "Invalid GUID string: {:?}", _0)]
+pub struct ParseGuidError(String);
+
+impl FromStr for Guid {
+    type Err = ParseGuidError;
+
+    fn from_str(guid_string: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseGuidError(guid_string.to_owned());
+        let digits = if guid_string.starts_with('{') && guid_string.ends_with('}') {
+            &guid_string[1..guid_string.len() - 1]
+        } else {
+            guid_string
+        };
+        let groups: Vec<&str> = digits.split('-').collect();
+
+        if groups.len() != 5
+            || groups
+                .iter()
+                .zip(&[8, 4, 4, 4, 12])
+                .any(|(group, &length)| {
+                    group.len() != length || !group.bytes().all(|digit| digit.is_ascii_hexdigit())
+                })
+        {
+            return Err(invalid());
+        }
+
+        let data1 = u32::from_str_radix(groups[0], 16).map_err(|_| invalid())?;
+        let data2 = u16::from_str_radix(groups[1], 16).map_err(|_| invalid())?;
+        let data3 = u16::from_str_radix(groups[2], 16).map_err(|_| invalid())?;
+        let tail = [groups[3], groups[4]].concat();
+        let mut data4 = [0; 8];
+
+        for (index, byte) in data4.iter_mut().enumerate() {
+            *byte =
+                u8::from_str_radix(&tail[2 * index..2 * index + 2], 16).map_err(|_| invalid())?;
+        }
+
+        Ok(Guid::from_fields(data1, data2, data3, data4))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The schema GUID of the Active Directory "user" class.
+    const USER_CLASS: [u8; 16] = [
+        0xBA, 0x7A, 0x96, 0xBF, 0xE6, 0x0D, 0xD0, 0x11, 0xA2, 0x85, 0x00, 0xAA, 0x00, 0x30, 0x49,
+        0xE2,
+    ];
+
+    #[test]
+    fn uses_the_mixed_endian_layout() {
+        let guid: Guid = "bf967aba-0de6-11d0-a285-00aa003049e2".parse().unwrap();
+
+        assert_eq!(guid.as_bytes(), &USER_CLASS);
+        assert_eq!(guid.to_string(), "bf967aba-0de6-11d0-a285-00aa003049e2");
+        assert_eq!(
+            "{BF967ABA-0DE6-11D0-A285-00AA003049E2}"
+                .parse::<Guid>()
+                .unwrap(),
+            guid
+        );
+        assert_eq!(Guid::from_prefix(&USER_CLASS), Some(guid));
+        assert_eq!(Guid::from_prefix(&USER_CLASS[..15]), None);
+    }
+
+    #[test]
+    fn rejects_malformed_guid_strings() {
+        let invalid = [
+            "",
+            "bf967aba-0de6-11d0-a285",
+            "bf967aba0de6-11d0-a285-00aa003049e2",
+            "bf967aba-0de6-11d0-a285-00aa003049e",
+            "bf967aba-0de6-11d0-a285-00aa003049g2",
+            "{bf967aba-0de6-11d0-a285-00aa003049e2",
+            "+f967aba-0de6-11d0-a285-00aa003049e2",
+        ];
+
+        for guid_string in &invalid {
+            assert!(guid_string.parse::<Guid>().is_err(), "{}", guid_string);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::iter::once;
+use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+use std::{ptr, slice};
+
+use winapi::shared::minwindef::DWORD;
+use winapi::shared::winerror::{ERROR_INVALID_SECURITY_DESCR, ERROR_SUCCESS};
+use winapi::um::accctrl::SE_FILE_OBJECT;
+use winapi::um::aclapi::{GetNamedSecurityInfoW, SetNamedSecurityInfoW};
+use winapi::um::securitybaseapi::GetSecurityDescriptorLength;
+use winapi::um::winbase::LocalFree;
+use winapi::um::winnt::{
+    DACL_SECURITY_INFORMATION, OWNER_SECURITY_INFORMATION, PACL,
+    PROTECTED_DACL_SECURITY_INFORMATION,
+};
+
+use super::{AccessControlListPtr, SecurityDescriptor};
+
+#[derive(Debug, Fail)]
+#[fail(display = "Failed to get security information. Error code: {}", win_error_code)]
+pub struct GetSecurityInformationError {
+    win_error_code: DWORD,
+}
+
+#[derive(Debug, Fail)]
+#[fail(
+    display = "Failed to set the discretionary access control list. Error code: {}", win_error_code
+)]
+pub struct SetDaclError {
+    win_error_code: DWORD,
+}
+
+pub trait PathExt {
+    fn security_information(&self) -> Result<SecurityDescriptor, GetSecurityInformationError>;
+    fn set_dacl<'a, A>(&self, dacl: A) -> Result<(), SetDaclError>
+    where
+        A: Into<AccessControlListPtr<'a>>;
+    fn set_protected_dacl<'a, A>(&self, dacl: A) -> Result<(), SetDaclError>
+    where
+        A: Into<AccessControlListPtr<'a>>;
+}
+
+impl<T> PathExt for T
+where
+    T: AsRef<Path>,
+{
+    fn security_information(&self) -> Result<SecurityDescriptor, GetSecurityInformationError> {
+        let mut security_descriptor = ptr::null_mut();
+
+        let file_path: Vec<u16> = self
+            .as_ref()
+            .as_os_str()
+            .encode_wide()
+            .chain(once(0))
+            .collect();
+
+        unsafe {
+            let get_security_info_result = GetNamedSecurityInfoW(
+                file_path.as_ptr(),
+                SE_FILE_OBJECT,
+                OWNER_SECURITY_INFORMATION | DACL_SECURITY_INFORMATION,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                &mut security_descriptor,
+            );
+
+            if get_security_info_result != ERROR_SUCCESS {
+                return Err(GetSecurityInformationError {
+                    win_error_code: get_security_info_result,
+                });
+            }
+
+            let length = GetSecurityDescriptorLength(security_descriptor) as usize;
+            let bytes = slice::from_raw_parts(security_descriptor as *const u8, length);
+            let parse_result = SecurityDescriptor::from_bytes(bytes);
+
+            if !LocalFree(security_descriptor).is_null() {
+                panic!("Failed to deallocate security descriptor");
+            }
+
+            parse_result.map_err(|_| GetSecurityInformationError {
+                win_error_code: ERROR_INVALID_SECURITY_DESCR,
+            })
+        }
+    }
+
+    fn set_dacl<'a, A>(&self, dacl: A) -> Result<(), SetDaclError>
+    where
+        A: Into<AccessControlListPtr<'a>>,
+    {
+        unsafe { set_dacl_of_path(self, dacl.into().as_ptr(), false) }
+    }
+
+    fn set_protected_dacl<'a, A>(&self, dacl: A) -> Result<(), SetDaclError>
+    where
+        A: Into<AccessControlListPtr<'a>>,
+    {
+        unsafe { set_dacl_of_path(self, dacl.into().as_ptr(), true) }
+    }
+}
+
+unsafe fn set_dacl_of_path<P: AsRef<Path>>(
+    path: &P,
+    dacl_ptr: PACL,
+    disable_inheritance: bool,
+) -> Result<(), SetDaclError> {
+    let mut file_path: Vec<u16> = path
+        .as_ref()
+        .as_os_str()
+        .encode_wide()
+        .chain(once(0))
+        .collect();
+
+    let mut flags = DACL_SECURITY_INFORMATION;
+
+    if disable_inheritance {
+        flags |= PROTECTED_DACL_SECURITY_INFORMATION;
+    }
+
+    let result = SetNamedSecurityInfoW(
+        file_path.as_mut_ptr(),
+        SE_FILE_OBJECT,
+        flags,
+        ptr::null_mut(),
+        ptr::null_mut(),
+        dacl_ptr,
+        ptr::null_mut(),
+    );
+
+    match result {
+        ERROR_SUCCESS => Ok(()),
+        win_error_code => Err(SetDaclError { win_error_code }),
+    }
+}
@@ -1,125 +1,314 @@
-use std::ptr;
+use bytes::{patch_u32, read_u16, read_u32, write_u16, write_u32};
 
-use winapi::shared::minwindef::{BOOL, DWORD, FALSE, TRUE};
-use winapi::um::errhandlingapi::GetLastError;
-use winapi::um::securitybaseapi::SetSecurityDescriptorDacl;
-use winapi::um::winbase::LocalFree;
-use winapi::um::winnt::{PACL, PSECURITY_DESCRIPTOR, SECURITY_DESCRIPTOR, SE_DACL_PROTECTED};
+use super::{AccessControlList, AccessControlListPtr, SecurityId, SecurityIdPtr};
 
-use super::{AccessControlList, AccessControlListPtr, SecurityIdPtr};
+const SECURITY_DESCRIPTOR_REVISION: u8 = 1;
+const SECURITY_DESCRIPTOR_HEADER_SIZE: usize = 20;
 
-#[derive(Debug, Fail)]
-#[fail(
-    display = "Failed to set security descriptor's discretionary access control list. Error code: {}",
-    win_error_code
-)]
-pub struct SetDaclError {
-    win_error_code: DWORD,
-}
+const OWNER_OFFSET: usize = 4;
+const GROUP_OFFSET: usize = 8;
+const SACL_OFFSET: usize = 12;
+const DACL_OFFSET: usize = 16;
 
-enum InternalAclPtr {
-    PartOfDescriptor(AccessControlListPtr<'static>),
-    ExternallyProvided(AccessControlList),
-}
+const SE_DACL_PRESENT: u16 = 0x0004;
+const SE_DACL_DEFAULTED: u16 = 0x0008;
+const SE_SACL_PRESENT: u16 = 0x0010;
+const SE_DACL_PROTECTED: u16 = 0x1000;
+const SE_SELF_RELATIVE: u16 = 0x8000;
+
+#[derive(Debug, Fail)]
+#[fail(display = "Invalid self-relative security descriptor")]
+pub struct InvalidSecurityDescriptorError;
 
 pub struct SecurityDescriptor {
-    security_descriptor: PSECURITY_DESCRIPTOR,
-    dacl: Option<InternalAclPtr>,
+    control: u16,
+    owner: Option<SecurityId>,
+    group: Option<SecurityId>,
+    sacl: Option<AccessControlList>,
+    dacl: Option<AccessControlList>,
 }
 
 impl SecurityDescriptor {
-    pub unsafe fn new(security_descriptor: PSECURITY_DESCRIPTOR) -> Self {
-        let dacl = Self::extract_dacl(security_descriptor as *const SECURITY_DESCRIPTOR);
+    pub fn new() -> Self {
+        SecurityDescriptor {
+            control: SE_SELF_RELATIVE,
+            owner: None,
+            group: None,
+            sacl: None,
+            dacl: None,
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_parts(
+        control: u16,
+        owner: Option<SecurityId>,
+        group: Option<SecurityId>,
+        sacl: Option<AccessControlList>,
+        dacl: Option<AccessControlList>,
+    ) -> Self {
+        // A present flag without a list is a NULL ACL, so it is kept as given.
+        let mut control = control | SE_SELF_RELATIVE;
+
+        if sacl.is_some() {
+            control |= SE_SACL_PRESENT;
+        }
+
+        if dacl.is_some() {
+            control |= SE_DACL_PRESENT;
+        }
 
         SecurityDescriptor {
-            security_descriptor,
+            control,
+            owner,
+            group,
+            sacl,
             dacl,
         }
     }
 
-    unsafe fn extract_dacl(
-        security_descriptor: *const SECURITY_DESCRIPTOR,
-    ) -> Option<InternalAclPtr> {
-        let security_descriptor = security_descriptor as *const SECURITY_DESCRIPTOR;
-        let dacl = (*security_descriptor).Dacl;
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InvalidSecurityDescriptorError> {
+        if bytes.len() < SECURITY_DESCRIPTOR_HEADER_SIZE || bytes[0] != SECURITY_DESCRIPTOR_REVISION
+        {
+            return Err(InvalidSecurityDescriptorError);
+        }
+
+        let control = read_u16(bytes, 2).ok_or(InvalidSecurityDescriptorError)?;
 
-        if dacl.is_null() {
+        if control & SE_SELF_RELATIVE == 0 {
+            return Err(InvalidSecurityDescriptorError);
+        }
+
+        let owner = Self::extract_sid(bytes, OWNER_OFFSET)?;
+        let group = Self::extract_sid(bytes, GROUP_OFFSET)?;
+        let sacl = if control & SE_SACL_PRESENT != 0 {
+            Self::extract_acl(bytes, SACL_OFFSET)?
+        } else {
             None
+        };
+        let dacl = if control & SE_DACL_PRESENT != 0 {
+            Self::extract_acl(bytes, DACL_OFFSET)?
         } else {
-            Some(InternalAclPtr::PartOfDescriptor(AccessControlListPtr::new(
-                dacl,
-            )))
-        }
+            None
+        };
+
+        Ok(SecurityDescriptor {
+            control,
+            owner,
+            group,
+            sacl,
+            dacl,
+        })
     }
 
-    pub fn owner<'a>(&'a self) -> Option<SecurityIdPtr<'a>> {
-        unsafe {
-            let security_descriptor = self.security_descriptor as *const SECURITY_DESCRIPTOR;
-            let owner = (*security_descriptor).Owner;
-
-            if owner.is_null() {
-                None
-            } else {
-                Some(SecurityIdPtr::new(owner))
-            }
+    fn extract_sid(
+        bytes: &[u8],
+        offset_position: usize,
+    ) -> Result<Option<SecurityId>, InvalidSecurityDescriptorError> {
+        match Self::extract_offset(bytes, offset_position)? {
+            Some(offset) => SecurityIdPtr::from_prefix(&bytes[offset..])
+                .map(|sid| Some(sid.to_security_id()))
+                .ok_or(InvalidSecurityDescriptorError),
+            None => Ok(None),
         }
     }
 
-    pub fn dacl<'a>(&'a self) -> Option<AccessControlListPtr<'a>> {
-        match self.dacl {
-            Some(InternalAclPtr::PartOfDescriptor(ref acl)) => unsafe {
-                Some(AccessControlListPtr::new(acl.as_ptr()))
-            },
-            Some(InternalAclPtr::ExternallyProvided(ref acl)) => unsafe {
-                Some(AccessControlListPtr::new(acl.as_ptr()))
-            },
-            None => None,
+    fn extract_acl(
+        bytes: &[u8],
+        offset_position: usize,
+    ) -> Result<Option<AccessControlList>, InvalidSecurityDescriptorError> {
+        match Self::extract_offset(bytes, offset_position)? {
+            Some(offset) => AccessControlListPtr::from_prefix(&bytes[offset..])
+                .map(|acl| Some(acl.to_access_control_list()))
+                .ok_or(InvalidSecurityDescriptorError),
+            None => Ok(None),
         }
     }
 
-    pub fn is_dacl_protected(&self) -> bool {
-        unsafe {
-            let security_descriptor = self.security_descriptor as *const SECURITY_DESCRIPTOR;
+    fn extract_offset(
+        bytes: &[u8],
+        offset_position: usize,
+    ) -> Result<Option<usize>, InvalidSecurityDescriptorError> {
+        let offset = read_u32(bytes, offset_position).ok_or(InvalidSecurityDescriptorError)?;
 
-            (*security_descriptor).Control & SE_DACL_PROTECTED != 0
+        match offset as usize {
+            0 => Ok(None),
+            offset if offset < SECURITY_DESCRIPTOR_HEADER_SIZE || offset >= bytes.len() => {
+                Err(InvalidSecurityDescriptorError)
+            }
+            offset => Ok(Some(offset)),
         }
     }
 
-    pub fn set_dacl(&mut self, dacl: Option<AccessControlList>) -> Result<(), SetDaclError> {
-        unsafe {
-            if let Some(dacl) = dacl {
-                self.set_dacl_ptr(dacl.as_ptr())?;
-                self.dacl = Some(InternalAclPtr::ExternallyProvided(dacl));
-            } else {
-                self.set_dacl_ptr(ptr::null_mut())?;
-                self.dacl = None;
-            }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(SECURITY_DESCRIPTOR_HEADER_SIZE);
+
+        bytes.push(SECURITY_DESCRIPTOR_REVISION);
+        bytes.push(0);
+        write_u16(&mut bytes, self.control | SE_SELF_RELATIVE);
+
+        for _ in 0..4 {
+            write_u32(&mut bytes, 0);
         }
 
-        Ok(())
+        if let Some(ref sacl) = self.sacl {
+            Self::append(&mut bytes, SACL_OFFSET, sacl.as_acl_ptr().as_bytes());
+        }
+
+        if let Some(ref dacl) = self.dacl {
+            Self::append(&mut bytes, DACL_OFFSET, dacl.as_acl_ptr().as_bytes());
+        }
+
+        if let Some(ref owner) = self.owner {
+            Self::append(&mut bytes, OWNER_OFFSET, owner.as_sid_ptr().as_bytes());
+        }
+
+        if let Some(ref group) = self.group {
+            Self::append(&mut bytes, GROUP_OFFSET, group.as_sid_ptr().as_bytes());
+        }
+
+        bytes
+    }
+
+    fn append(bytes: &mut Vec<u8>, offset_position: usize, component: &[u8]) {
+        let offset = bytes.len() as u32;
+
+        patch_u32(bytes, offset_position, offset);
+        bytes.extend_from_slice(component);
+    }
+
+    pub fn control(&self) -> u16 {
+        self.control
+    }
+
+    pub fn owner<'a>(&'a self) -> Option<SecurityIdPtr<'a>> {
+        self.owner.as_ref().map(SecurityId::as_sid_ptr)
+    }
+
+    pub fn group<'a>(&'a self) -> Option<SecurityIdPtr<'a>> {
+        self.group.as_ref().map(SecurityId::as_sid_ptr)
+    }
+
+    pub fn dacl<'a>(&'a self) -> Option<AccessControlListPtr<'a>> {
+        self.dacl.as_ref().map(AccessControlList::as_acl_ptr)
     }
 
-    unsafe fn set_dacl_ptr(&mut self, dacl_ptr: PACL) -> Result<(), SetDaclError> {
-        let ptr_is_non_null: BOOL = if dacl_ptr.is_null() { FALSE } else { TRUE };
+    pub fn sacl<'a>(&'a self) -> Option<AccessControlListPtr<'a>> {
+        self.sacl.as_ref().map(AccessControlList::as_acl_ptr)
+    }
 
-        let result =
-            SetSecurityDescriptorDacl(self.security_descriptor, ptr_is_non_null, dacl_ptr, TRUE);
+    pub fn is_dacl_protected(&self) -> bool {
+        self.control & SE_DACL_PROTECTED != 0
+    }
 
-        match result {
-            FALSE => Err(SetDaclError {
-                win_error_code: GetLastError(),
-            }),
-            _ => Ok(()),
+    pub fn set_dacl(&mut self, dacl: Option<AccessControlList>) {
+        if dacl.is_some() {
+            self.control |= SE_DACL_PRESENT | SE_DACL_DEFAULTED;
+        } else {
+            self.control &= !SE_DACL_PRESENT;
         }
+
+        self.dacl = dacl;
     }
 }
 
-impl Drop for SecurityDescriptor {
-    fn drop(&mut self) {
-        unsafe {
-            if !LocalFree(self.security_descriptor as *mut _).is_null() {
-                panic!("Failed to deallocate security descriptor");
-            }
-        }
+impl Default for SecurityDescriptor {
+    fn default() -> Self {
+        SecurityDescriptor::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {AccessMask, WellKnownSid};
+
+    // O:BAG:SYD:(A;;0x1f01ff;;;WD), laid out the way Windows returns it, with the lists first.
+    const DESCRIPTOR: [u8; 76] = [
+        0x01, 0x00, 0x04, 0x80, 0x30, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, //
+        0x00, 0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, //
+        0x02, 0x00, 0x1C, 0x00, 0x01, 0x00, 0x00, 0x00, //
+        0x00, 0x00, 0x14, 0x00, 0xFF, 0x01, 0x1F, 0x00, //
+        0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, //
+        0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, //
+        0x20, 0x00, 0x00, 0x00, 0x20, 0x02, 0x00, 0x00, //
+        0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x12, 0x00, 0x00, 0x00,
+    ];
+
+    fn well_known(well_known_sid: WellKnownSid) -> SecurityId {
+        SecurityId::well_known(well_known_sid).unwrap()
+    }
+
+    #[test]
+    fn round_trips_self_relative_descriptors() {
+        let descriptor = SecurityDescriptor::from_bytes(&DESCRIPTOR).unwrap();
+        let dacl = descriptor.dacl().unwrap();
+        let entry = dacl.entries().next().unwrap();
+
+        assert_eq!(
+            descriptor.owner(),
+            Some(well_known(WellKnownSid::BuiltinAdministrators).as_sid_ptr())
+        );
+        assert_eq!(
+            descriptor.group(),
+            Some(well_known(WellKnownSid::LocalSystem).as_sid_ptr())
+        );
+        assert!(descriptor.sacl().is_none());
+        assert_eq!(dacl.num_entries(), 1);
+        assert_eq!(entry.access_mask(), AccessMask::from_bits(0x001F_01FF));
+        assert_eq!(
+            entry.trustee(),
+            Some(well_known(WellKnownSid::World).as_sid_ptr())
+        );
+        assert_eq!(descriptor.to_bytes(), &DESCRIPTOR[..]);
+    }
+
+    #[test]
+    fn keeps_null_dacls() {
+        let bytes = [
+            0x01, 0x00, 0x04, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let descriptor = SecurityDescriptor::from_bytes(&bytes).unwrap();
+
+        assert!(descriptor.dacl().is_none());
+        assert_eq!(descriptor.control() & SE_DACL_PRESENT, SE_DACL_PRESENT);
+        assert_eq!(descriptor.to_bytes(), &bytes[..]);
+    }
+
+    #[test]
+    fn replaces_the_dacl() {
+        let mut descriptor = SecurityDescriptor::new();
+
+        descriptor.set_dacl(Some(AccessControlList::new()));
+
+        let parsed = SecurityDescriptor::from_bytes(&descriptor.to_bytes()).unwrap();
+
+        assert_eq!(parsed.dacl().unwrap().num_entries(), 0);
+        assert!(parsed.owner().is_none());
+
+        descriptor.set_dacl(None);
+
+        assert_eq!(descriptor.control() & SE_DACL_PRESENT, 0);
+    }
+
+    #[test]
+    fn rejects_malformed_descriptors() {
+        let mut absolute = DESCRIPTOR;
+        let mut wrong_revision = DESCRIPTOR;
+        let mut owner_out_of_bounds = DESCRIPTOR;
+        let mut owner_inside_header = DESCRIPTOR;
+
+        absolute[3] = 0x00;
+        wrong_revision[0] = 2;
+        owner_out_of_bounds[4] = 0x4C;
+        owner_inside_header[4] = 0x10;
+
+        assert!(SecurityDescriptor::from_bytes(&DESCRIPTOR[..19]).is_err());
+        assert!(SecurityDescriptor::from_bytes(&DESCRIPTOR[..72]).is_err());
+        assert!(SecurityDescriptor::from_bytes(&absolute).is_err());
+        assert!(SecurityDescriptor::from_bytes(&wrong_revision).is_err());
+        assert!(SecurityDescriptor::from_bytes(&owner_out_of_bounds).is_err());
+        assert!(SecurityDescriptor::from_bytes(&owner_inside_header).is_err());
     }
 }
@@ -0,0 +1,1099 @@
+use std::fmt::{self, Formatter, Write};
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+
+use bytes::write_u16;
+use {
+    AccessControlEntry, AccessControlEntryPtr, AccessControlList, AccessControlListPtr,
+    AccessInheritance, AccessMask, AccessMode, AceFlags, AceType, ClaimAttributeFlags,
+    ClaimSecurityAttribute, ClaimValue, ConditionalExpression, ExplicitAccess, Guid,
+    MandatoryPolicy, SecurityDescriptor, SecurityId, SecurityIdPtr,
+};
+
+const DEFAULT_ACL_REVISION: u8 = 2;
+
+const ACCESS_MASK_NAMES: &[(&str, u32)] = &[
+    ("DELETE", 0x0001_0000),
+    ("READ_CONTROL", 0x0002_0000),
+    ("WRITE_DAC", 0x0004_0000),
+    ("WRITE_OWNER", 0x0008_0000),
+    ("SYNCHRONIZE", 0x0010_0000),
+    ("ACCESS_SYSTEM_SECURITY", 0x0100_0000),
+    ("MAXIMUM_ALLOWED", 0x0200_0000),
+    ("GENERIC_ALL", 0x1000_0000),
+    ("GENERIC_EXECUTE", 0x2000_0000),
+    ("GENERIC_WRITE", 0x4000_0000),
+    ("GENERIC_READ", 0x8000_0000),
+];
+
+const ACCESS_INHERITANCE_NAMES: &[(&str, u32)] = &[
+    ("OBJECTS_INHERIT", 0x1),
+    ("CONTAINERS_INHERIT", 0x2),
+    ("DONT_PROPAGATE", 0x4),
+    ("INHERIT_ONLY", 0x8),
+];
+
+const ACE_FLAG_NAMES: &[(&str, u32)] = &[
+    ("OBJECT_INHERIT", 0x01),
+    ("CONTAINER_INHERIT", 0x02),
+    ("NO_PROPAGATE_INHERIT", 0x04),
+    ("INHERIT_ONLY", 0x08),
+    ("INHERITED", 0x10),
+    ("CRITICAL", 0x20),
+    ("SUCCESSFUL_ACCESS", 0x40),
+    ("FAILED_ACCESS", 0x80),
+];
+
+const CONTROL_NAMES: &[(&str, u32)] = &[
+    ("OWNER_DEFAULTED", 0x0001),
+    ("GROUP_DEFAULTED", 0x0002),
+    ("DACL_PRESENT", 0x0004),
+    ("DACL_DEFAULTED", 0x0008),
+    ("SACL_PRESENT", 0x0010),
+    ("SACL_DEFAULTED", 0x0020),
+    ("DACL_AUTO_INHERIT_REQ", 0x0100),
+    ("SACL_AUTO_INHERIT_REQ", 0x0200),
+    ("DACL_AUTO_INHERITED", 0x0400),
+    ("SACL_AUTO_INHERITED", 0x0800),
+    ("DACL_PROTECTED", 0x1000),
+    ("SACL_PROTECTED", 0x2000),
+    ("RM_CONTROL_VALID", 0x4000),
+    ("SELF_RELATIVE", 0x8000),
+];
+
+const ACE_TYPE_NAMES: &[(&str, u8)] = &[
+    ("AccessAllowed", 0x00),
+    ("AccessDenied", 0x01),
+    ("SystemAudit", 0x02),
+    ("SystemAlarm", 0x03),
+    ("AccessAllowedCompound", 0x04),
+    ("AccessAllowedObject", 0x05),
+    ("AccessDeniedObject", 0x06),
+    ("SystemAuditObject", 0x07),
+    ("SystemAlarmObject", 0x08),
+    ("AccessAllowedCallback", 0x09),
+    ("AccessDeniedCallback", 0x0A),
+    ("AccessAllowedCallbackObject", 0x0B),
+    ("AccessDeniedCallbackObject", 0x0C),
+    ("SystemAuditCallback", 0x0D),
+    ("SystemAlarmCallback", 0x0E),
+    ("SystemAuditCallbackObject", 0x0F),
+    ("SystemAlarmCallbackObject", 0x10),
+    ("SystemMandatoryLabel", 0x11),
+    ("SystemResourceAttribute", 0x12),
+    ("SystemScopedPolicyId", 0x13),
+    ("SystemProcessTrustLabel", 0x14),
+    ("SystemAccessFilter", 0x15),
+];
+
+const MANDATORY_POLICY_NAMES: &[(&str, u32)] = &[
+    ("NO_WRITE_UP", 0x1),
+    ("NO_READ_UP", 0x2),
+    ("NO_EXECUTE_UP", 0x4),
+];
+
+const CLAIM_ATTRIBUTE_FLAG_NAMES: &[(&str, u32)] = &[
+    ("NON_INHERITABLE", 0x0001),
+    ("VALUE_CASE_SENSITIVE", 0x0002),
+    ("USE_FOR_DENY_ONLY", 0x0004),
+    ("DISABLED_BY_DEFAULT", 0x0008),
+    ("DISABLED", 0x0010),
+    ("MANDATORY", 0x0020),
+];
+
+const ACCESS_MODE_NAMES: &[(&str, AccessMode)] = &[
+    ("NotUsed", AccessMode::NotUsed),
+    ("GrantAccess", AccessMode::GrantAccess),
+    ("SetAccess", AccessMode::SetAccess),
+    ("DenyAccess", AccessMode::DenyAccess),
+    ("RevokeAccess", AccessMode::RevokeAccess),
+    ("SetAuditSuccess", AccessMode::SetAuditSuccess),
+    ("SetAuditFailure", AccessMode::SetAuditFailure),
+];
+
+fn serialize_flags<S: Serializer>(
+    serializer: S,
+    bits: u32,
+    names: &[(&str, u32)],
+) -> Result<S::Ok, S::Error> {
+    if !serializer.is_human_readable() {
+        return serializer.serialize_u32(bits);
+    }
+
+    let mut remaining_bits = bits;
+    let mut flags = Vec::new();
+
+    for &(name, flag) in names {
+        if remaining_bits & flag == flag {
+            flags.push(name.to_owned());
+            remaining_bits &= !flag;
+        }
+    }
+
+    if remaining_bits != 0 {
+        flags.push(format!("0x{:X}", remaining_bits));
+    }
+
+    serializer.collect_seq(flags)
+}
+
+fn deserialize_flags<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    names: &[(&str, u32)],
+) -> Result<u32, D::Error> {
+    if !deserializer.is_human_readable() {
+        return u32::deserialize(deserializer);
+    }
+
+    let mut bits = 0;
+
+    for flag in Vec::<String>::deserialize(deserializer)? {
+        bits |= names
+            .iter()
+            .find(|&&(name, _)| name == flag)
+            .map(|&(_, value)| value)
+            .or_else(|| parse_hexadecimal(&flag))
+            .ok_or_else(|| de::Error::custom(format!("unknown flag {:?}", flag)))?;
+    }
+
+    Ok(bits)
+}
+
+fn parse_hexadecimal(value: &str) -> Option<u32> {
+    if value.starts_with("0x") || value.starts_with("0X") {
+        u32::from_str_radix(&value[2..], 16).ok()
+    } else {
+        None
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    hex.as_bytes()
+        .chunks(2)
+        .map(|digits| {
+            let digits = ::std::str::from_utf8(digits)
+                .ok()
+                .filter(|digits| digits.len() == 2)?;
+
+            u8::from_str_radix(digits, 16).ok()
+        })
+        .collect()
+}
+
+macro_rules! flags_repr {
+    ($repr:ident($raw:ty), $names:expr) => {
+        #[derive(Clone, Copy)]
+        struct $repr($raw);
+
+        impl Serialize for $repr {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serialize_flags(serializer, u32::from(self.0), $names)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $repr {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let bits = deserialize_flags(deserializer, $names)?;
+
+                if bits > u32::from(<$raw>::MAX) {
+                    return Err(de::Error::custom(format!(
+                        "flags out of range: 0x{:X}",
+                        bits
+                    )));
+                }
+
+                Ok($repr(bits as $raw))
+            }
+        }
+    };
+}
+
+flags_repr!(RawAccessMask(u32), ACCESS_MASK_NAMES);
+flags_repr!(RawAccessInheritance(u32), ACCESS_INHERITANCE_NAMES);
+flags_repr!(RawAceFlags(u8), ACE_FLAG_NAMES);
+flags_repr!(RawControl(u16), CONTROL_NAMES);
+flags_repr!(RawMandatoryPolicy(u32), MANDATORY_POLICY_NAMES);
+flags_repr!(RawClaimAttributeFlags(u32), CLAIM_ATTRIBUTE_FLAG_NAMES);
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str("a byte array")
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        Ok(bytes.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(bytes)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut sequence: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(sequence.size_hint().unwrap_or(0));
+
+        while let Some(byte) = sequence.next_element()? {
+            bytes.push(byte);
+        }
+
+        Ok(bytes)
+    }
+}
+
+impl Serialize for AccessMask {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RawAccessMask(self.bits()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AccessMask {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let RawAccessMask(bits) = RawAccessMask::deserialize(deserializer)?;
+
+        Ok(AccessMask::from_raw(bits))
+    }
+}
+
+impl Serialize for AccessInheritance {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RawAccessInheritance(self.bits()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AccessInheritance {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let RawAccessInheritance(bits) = RawAccessInheritance::deserialize(deserializer)?;
+
+        AccessInheritance::from_bits(bits)
+            .ok_or_else(|| de::Error::custom(format!("unsupported inheritance: 0x{:X}", bits)))
+    }
+}
+
+impl Serialize for AccessMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_u32(*self as u32);
+        }
+
+        let name = ACCESS_MODE_NAMES
+            .iter()
+            .find(|&&(_, mode)| mode == *self)
+            .map(|&(name, _)| name)
+            .expect("Missing access mode name");
+
+        serializer.serialize_str(name)
+    }
+}
+
+impl<'de> Deserialize<'de> for AccessMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let name = String::deserialize(deserializer)?;
+
+            ACCESS_MODE_NAMES
+                .iter()
+                .find(|&&(mode_name, _)| mode_name == name)
+                .map(|&(_, mode)| mode)
+                .ok_or_else(|| de::Error::custom(format!("unknown access mode {:?}", name)))
+        } else {
+            let value = u32::deserialize(deserializer)?;
+
+            ACCESS_MODE_NAMES
+                .iter()
+                .find(|&&(_, mode)| mode as u32 == value)
+                .map(|&(_, mode)| mode)
+                .ok_or_else(|| de::Error::custom(format!("unknown access mode {}", value)))
+        }
+    }
+}
+
+impl<'a> Serialize for SecurityIdPtr<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_bytes(self.as_bytes())
+        }
+    }
+}
+
+impl Serialize for SecurityId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_sid_ptr().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecurityId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?
+                .parse()
+                .map_err(de::Error::custom)
+        } else {
+            let bytes = deserializer.deserialize_bytes(BytesVisitor)?;
+
+            SecurityId::from_bytes(&bytes).map_err(de::Error::custom)
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ExplicitAccessRef<'a> {
+    permissions: AccessMask,
+    mode: AccessMode,
+    inheritance: AccessInheritance,
+    trustee: SecurityIdPtr<'a>,
+}
+
+#[derive(Deserialize)]
+struct ExplicitAccessRepr {
+    permissions: AccessMask,
+    mode: AccessMode,
+    inheritance: AccessInheritance,
+    trustee: SecurityId,
+}
+
+impl<'trustee> Serialize for ExplicitAccess<'trustee> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ExplicitAccessRef {
+            permissions: self.permissions(),
+            mode: self.mode(),
+            inheritance: self.inheritance(),
+            trustee: self.trustee(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, 'trustee> Deserialize<'de> for ExplicitAccess<'trustee> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = ExplicitAccessRepr::deserialize(deserializer)?;
+
+        Ok(ExplicitAccess::with_owned_trustee(
+            repr.permissions,
+            repr.mode,
+            repr.inheritance,
+            repr.trustee,
+        ))
+    }
+}
+
+#[derive(Clone, Copy)]
+struct RawAceType(u8);
+
+impl Serialize for RawAceType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match ACE_TYPE_NAMES
+            .iter()
+            .find(|&&(_, ace_type)| ace_type == self.0)
+        {
+            Some(&(name, _)) => serializer.serialize_str(name),
+            None => serializer.collect_str(&format_args!("0x{:02X}", self.0)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RawAceType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+
+        ACE_TYPE_NAMES
+            .iter()
+            .find(|&&(ace_type_name, _)| ace_type_name == name)
+            .map(|&(_, ace_type)| ace_type)
+            .or_else(|| {
+                parse_hexadecimal(&name)
+                    .filter(|&value| value <= 0xFF)
+                    .map(|value| value as u8)
+            })
+            .map(RawAceType)
+            .ok_or_else(|| {
+                de::Error::custom(format!("unknown access control entry type {:?}", name))
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+enum ClaimValueRepr {
+    Int64(i64),
+    UInt64(u64),
+    String(String),
+    Fqbn { version: u64, name: String },
+    Sid(SecurityId),
+    Boolean(bool),
+    OctetString(String),
+}
+
+impl<'a> From<&'a ClaimValue> for ClaimValueRepr {
+    fn from(value: &'a ClaimValue) -> Self {
+        match *value {
+            ClaimValue::Int64(value) => ClaimValueRepr::Int64(value),
+            ClaimValue::UInt64(value) => ClaimValueRepr::UInt64(value),
+            ClaimValue::String(ref value) => ClaimValueRepr::String(value.clone()),
+            ClaimValue::Fqbn { version, ref name } => ClaimValueRepr::Fqbn {
+                version,
+                name: name.clone(),
+            },
+            ClaimValue::Sid(ref sid) => ClaimValueRepr::Sid(sid.clone()),
+            ClaimValue::Boolean(value) => ClaimValueRepr::Boolean(value),
+            ClaimValue::OctetString(ref bytes) => ClaimValueRepr::OctetString(to_hex(bytes)),
+        }
+    }
+}
+
+impl ClaimValueRepr {
+    fn into_value(self) -> Result<ClaimValue, String> {
+        Ok(match self {
+            ClaimValueRepr::Int64(value) => ClaimValue::Int64(value),
+            ClaimValueRepr::UInt64(value) => ClaimValue::UInt64(value),
+            ClaimValueRepr::String(value) => ClaimValue::String(value),
+            ClaimValueRepr::Fqbn { version, name } => ClaimValue::Fqbn { version, name },
+            ClaimValueRepr::Sid(sid) => ClaimValue::Sid(sid),
+            ClaimValueRepr::Boolean(value) => ClaimValue::Boolean(value),
+            ClaimValueRepr::OctetString(hex) => ClaimValue::OctetString(
+                from_hex(&hex).ok_or_else(|| format!("invalid octet string {:?}", hex))?,
+            ),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ClaimAttributeRepr {
+    name: String,
+    flags: RawClaimAttributeFlags,
+    values: Vec<ClaimValueRepr>,
+}
+
+impl<'a> From<&'a ClaimSecurityAttribute> for ClaimAttributeRepr {
+    fn from(attribute: &'a ClaimSecurityAttribute) -> Self {
+        ClaimAttributeRepr {
+            name: attribute.name().to_owned(),
+            flags: RawClaimAttributeFlags(attribute.flags().bits()),
+            values: attribute
+                .values()
+                .iter()
+                .map(ClaimValueRepr::from)
+                .collect(),
+        }
+    }
+}
+
+impl ClaimAttributeRepr {
+    fn into_attribute(self) -> Result<ClaimSecurityAttribute, String> {
+        let flags = ClaimAttributeFlags::from_raw(self.flags.0);
+        let values = self
+            .values
+            .into_iter()
+            .map(ClaimValueRepr::into_value)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        ClaimSecurityAttribute::new(&self.name, flags, values).map_err(|error| error.to_string())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct AceRepr {
+    #[serde(rename = "type")]
+    ace_type: RawAceType,
+    flags: RawAceFlags,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mask: Option<AccessMask>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    policy: Option<RawMandatoryPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    object_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inherited_object_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    trustee: Option<SecurityId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    condition: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    attribute: Option<ClaimAttributeRepr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    application_data: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<String>,
+}
+
+impl AceRepr {
+    fn raw(ace_type: u8, flags: AceFlags, data: &[u8]) -> Self {
+        AceRepr {
+            ace_type: RawAceType(ace_type),
+            flags: RawAceFlags(flags.bits()),
+            mask: None,
+            policy: None,
+            object_type: None,
+            inherited_object_type: None,
+            trustee: None,
+            condition: None,
+            attribute: None,
+            application_data: None,
+            data: Some(to_hex(data)),
+        }
+    }
+
+    fn from_entry(entry: &AccessControlEntry) -> Self {
+        if let AccessControlEntry::Unknown {
+            ace_type,
+            flags,
+            ref data,
+        } = *entry
+        {
+            return AceRepr::raw(ace_type, flags, data);
+        }
+
+        let ace_type = entry
+            .ace_type()
+            .expect("Known access control entry without a type");
+        let mask = entry.access_mask().unwrap_or_else(AccessMask::empty);
+        let mut repr = AceRepr {
+            ace_type: RawAceType(ace_type as u8),
+            flags: RawAceFlags(entry.flags().bits()),
+            mask: Some(mask),
+            policy: None,
+            object_type: entry.object_type().map(|guid| guid.to_string()),
+            inherited_object_type: entry.inherited_object_type().map(|guid| guid.to_string()),
+            trustee: entry.trustee().map(SecurityIdPtr::to_security_id),
+            condition: None,
+            attribute: None,
+            application_data: None,
+            data: None,
+        };
+
+        if ace_type == AceType::SystemMandatoryLabel
+            && MandatoryPolicy::from_bits(mask.bits()).is_some()
+        {
+            repr.mask = None;
+            repr.policy = Some(RawMandatoryPolicy(mask.bits()));
+        }
+
+        let application_data = entry.application_data();
+
+        if ace_type == AceType::SystemResourceAttribute {
+            repr.attribute = readable_attribute(application_data);
+        } else {
+            repr.condition = readable_condition(application_data);
+        }
+
+        if !application_data.is_empty() && repr.attribute.is_none() && repr.condition.is_none() {
+            repr.application_data = Some(to_hex(application_data));
+        }
+
+        repr
+    }
+
+    fn into_entry(self) -> Result<AccessControlEntry, String> {
+        let flags = AceFlags::from_raw(self.flags.0);
+
+        if let Some(data) = self.data {
+            if self.mask.is_some()
+                || self.policy.is_some()
+                || self.object_type.is_some()
+                || self.inherited_object_type.is_some()
+                || self.trustee.is_some()
+                || self.condition.is_some()
+                || self.attribute.is_some()
+                || self.application_data.is_some()
+            {
+                return Err(
+                    "raw access control entries only have a type, flags and data".to_owned(),
+                );
+            }
+
+            return Ok(AccessControlEntry::Unknown {
+                ace_type: self.ace_type.0,
+                flags,
+                data: from_hex(&data).ok_or_else(|| format!("invalid entry data {:?}", data))?,
+            });
+        }
+
+        let ace_type = AceType::from_raw(self.ace_type.0).ok_or_else(|| {
+            format!(
+                "access control entry type 0x{:02X} needs raw data",
+                self.ace_type.0
+            )
+        })?;
+        let mask = match (self.mask, self.policy) {
+            (Some(mask), None) => mask,
+            (None, Some(RawMandatoryPolicy(policy)))
+                if ace_type == AceType::SystemMandatoryLabel =>
+            {
+                MandatoryPolicy::from_bits(policy)
+                    .map(|policy| AccessMask::from_bits_truncate(policy.bits()))
+                    .ok_or_else(|| format!("unsupported mandatory policy: 0x{:X}", policy))?
+            }
+            _ => {
+                return Err("access control entry needs either a mask or a label policy".to_owned())
+            }
+        };
+        let application_data = match (self.condition, self.attribute, self.application_data) {
+            (None, None, None) => Vec::new(),
+            (Some(condition), None, None) => condition
+                .parse::<ConditionalExpression>()
+                .map_err(|error| error.to_string())?
+                .to_bytes(),
+            (None, Some(attribute), None) => attribute.into_attribute()?.to_bytes(),
+            (None, None, Some(data)) => {
+                from_hex(&data).ok_or_else(|| format!("invalid application data {:?}", data))?
+            }
+            _ => {
+                return Err(
+                    "access control entry has more than one kind of application data".to_owned(),
+                )
+            }
+        };
+
+        let mut builder = AccessControlEntry::builder(ace_type)
+            .flags(flags)
+            .mask(mask)
+            .application_data(application_data);
+
+        if let Some(ref trustee) = self.trustee {
+            builder = builder.trustee(trustee);
+        }
+
+        if let Some(object_type) = self.object_type {
+            builder = builder.object_type(parse_guid(&object_type)?);
+        }
+
+        if let Some(inherited_object_type) = self.inherited_object_type {
+            builder = builder.inherited_object_type(parse_guid(&inherited_object_type)?);
+        }
+
+        builder.build().map_err(|error| error.to_string())
+    }
+}
+
+fn parse_guid(guid: &str) -> Result<Guid, String> {
+    guid.parse::<Guid>().map_err(|error| error.to_string())
+}
+
+/// Checks if `data` is exactly `bytes` padded to the access control entry alignment, so that the
+/// readable form can be encoded back into the same bytes.
+fn is_padded_form(data: &[u8], bytes: &[u8]) -> bool {
+    data.len() == (bytes.len() + 3) & !3
+        && data.starts_with(bytes)
+        && data[bytes.len()..].iter().all(|&byte| byte == 0)
+}
+
+fn readable_condition(data: &[u8]) -> Option<String> {
+    let mut condition = String::new();
+
+    // Conditions holding a string literal with a quote have no text form.
+    write!(
+        condition,
+        "{}",
+        ConditionalExpression::from_bytes(data).ok()?
+    )
+    .ok()?;
+
+    let reparsed: ConditionalExpression = condition.parse().ok()?;
+
+    if is_padded_form(data, &reparsed.to_bytes()) {
+        Some(condition)
+    } else {
+        None
+    }
+}
+
+fn readable_attribute(data: &[u8]) -> Option<ClaimAttributeRepr> {
+    let attribute = ClaimSecurityAttribute::from_bytes(data).ok()?;
+
+    if is_padded_form(data, &attribute.to_bytes()) {
+        Some(ClaimAttributeRepr::from(&attribute))
+    } else {
+        None
+    }
+}
+
+impl Serialize for AccessControlEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            AceRepr::from_entry(self).serialize(serializer)
+        } else {
+            serializer.serialize_bytes(&self.to_bytes().map_err(ser::Error::custom)?)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for AccessControlEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            return AceRepr::deserialize(deserializer)?
+                .into_entry()
+                .map_err(de::Error::custom);
+        }
+
+        let bytes = deserializer.deserialize_bytes(BytesVisitor)?;
+
+        AccessControlEntryPtr::from_prefix(&bytes)
+            .filter(|entry| entry.size() == bytes.len())
+            .ok_or_else(|| de::Error::custom("invalid access control entry"))?
+            .to_access_control_entry()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl<'a> Serialize for AccessControlEntryPtr<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_bytes(self.as_bytes());
+        }
+
+        let bytes = self.as_bytes();
+
+        match self.to_access_control_entry() {
+            Ok(ref entry) if entry.to_bytes().ok().as_deref() == Some(bytes) => {
+                AceRepr::from_entry(entry).serialize(serializer)
+            }
+            _ => AceRepr::raw(bytes[0], self.flags(), &bytes[4..]).serialize(serializer),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AclRef<'a> {
+    revision: u8,
+    entries: Vec<AccessControlEntryPtr<'a>>,
+}
+
+#[derive(Deserialize)]
+struct AclRepr {
+    #[serde(default = "default_acl_revision")]
+    revision: u8,
+    entries: Vec<AccessControlEntry>,
+}
+
+fn default_acl_revision() -> u8 {
+    DEFAULT_ACL_REVISION
+}
+
+impl<'a> Serialize for AccessControlListPtr<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_bytes(self.as_bytes());
+        }
+
+        AclRef {
+            revision: self.as_bytes()[0],
+            entries: self.entries().collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl Serialize for AccessControlList {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_acl_ptr().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AccessControlList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if !deserializer.is_human_readable() {
+            let bytes = deserializer.deserialize_bytes(BytesVisitor)?;
+
+            return AccessControlList::from_bytes(&bytes).map_err(de::Error::custom);
+        }
+
+        let repr = AclRepr::deserialize(deserializer)?;
+        let mut entries = Vec::new();
+
+        for entry in &repr.entries {
+            entry.validate().map_err(de::Error::custom)?;
+            entry.encode(&mut entries);
+        }
+
+        let size = 8 + entries.len();
+
+        if size > usize::from(u16::MAX) || repr.entries.len() > usize::from(u16::MAX) {
+            return Err(de::Error::custom("access control list is too large"));
+        }
+
+        let mut bytes = Vec::with_capacity(size);
+
+        bytes.push(repr.revision);
+        bytes.push(0);
+        write_u16(&mut bytes, size as u16);
+        write_u16(&mut bytes, repr.entries.len() as u16);
+        write_u16(&mut bytes, 0);
+        bytes.extend_from_slice(&entries);
+
+        AccessControlList::from_bytes(&bytes).map_err(de::Error::custom)
+    }
+}
+
+#[derive(Serialize)]
+struct SecurityDescriptorRef<'a> {
+    control: RawControl,
+    owner: Option<SecurityIdPtr<'a>>,
+    group: Option<SecurityIdPtr<'a>>,
+    sacl: Option<AccessControlListPtr<'a>>,
+    dacl: Option<AccessControlListPtr<'a>>,
+}
+
+#[derive(Deserialize)]
+struct SecurityDescriptorRepr {
+    control: RawControl,
+    #[serde(default)]
+    owner: Option<SecurityId>,
+    #[serde(default)]
+    group: Option<SecurityId>,
+    #[serde(default)]
+    sacl: Option<AccessControlList>,
+    #[serde(default)]
+    dacl: Option<AccessControlList>,
+}
+
+impl Serialize for SecurityDescriptor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_bytes(&self.to_bytes());
+        }
+
+        SecurityDescriptorRef {
+            control: RawControl(self.control()),
+            owner: self.owner(),
+            group: self.group(),
+            sacl: self.sacl(),
+            dacl: self.dacl(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecurityDescriptor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if !deserializer.is_human_readable() {
+            let bytes = deserializer.deserialize_bytes(BytesVisitor)?;
+
+            return SecurityDescriptor::from_bytes(&bytes).map_err(de::Error::custom);
+        }
+
+        let repr = SecurityDescriptorRepr::deserialize(deserializer)?;
+
+        Ok(SecurityDescriptor::from_parts(
+            repr.control.0,
+            repr.owner,
+            repr.group,
+            repr.sacl,
+            repr.dacl,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bincode;
+    use serde_json::{self, Value};
+
+    use super::*;
+    use {
+        BinaryOperator, ClaimSource, ConditionalLiteral, IntegrityLevel, MandatoryLabel,
+        WellKnownSid,
+    };
+
+    const DACL_PROTECTED: u16 = 0x1000;
+    const DACL_PRESENT: u16 = 0x0004;
+
+    fn sid(well_known_sid: WellKnownSid) -> SecurityId {
+        SecurityId::well_known(well_known_sid).unwrap()
+    }
+
+    fn security_descriptor() -> SecurityDescriptor {
+        let condition: ConditionalExpression = "(@User.clearance >= 5)".parse().unwrap();
+        let attribute = ClaimSecurityAttribute::new(
+            "Secrecy",
+            ClaimAttributeFlags::VALUE_CASE_SENSITIVE,
+            vec![ClaimValue::String("High".to_owned())],
+        )
+        .unwrap();
+        let mut dacl = AccessControlList::new();
+        let mut sacl = AccessControlList::new();
+
+        let dacl_entries = vec![
+            AccessControlEntry::builder(AceType::AccessAllowed)
+                .flags(AceFlags::CONTAINER_INHERIT)
+                .mask(AccessMask::GENERIC_READ | AccessMask::SYNCHRONIZE)
+                .trustee(&sid(WellKnownSid::AuthenticatedUser))
+                .build()
+                .unwrap(),
+            AccessControlEntry::builder(AceType::AccessDeniedObject)
+                .mask(AccessMask::WRITE_DAC)
+                .trustee(&sid(WellKnownSid::World))
+                .object_type("bf967aba-0de6-11d0-a285-00aa003049e2".parse().unwrap())
+                .build()
+                .unwrap(),
+            AccessControlEntry::builder(AceType::AccessAllowedCallback)
+                .mask(AccessMask::GENERIC_ALL)
+                .trustee(&sid(WellKnownSid::BuiltinUsers))
+                .condition(&condition)
+                .build()
+                .unwrap(),
+            AccessControlEntry::Unknown {
+                ace_type: 0x15,
+                flags: AceFlags::INHERITED,
+                data: vec![1, 2, 3, 4],
+            },
+        ];
+        let sacl_entries = vec![
+            AccessControlEntry::mandatory_label(
+                MandatoryLabel::new(IntegrityLevel::High, MandatoryPolicy::NO_WRITE_UP),
+                AceFlags::empty(),
+            ),
+            AccessControlEntry::resource_attribute(&attribute, AceFlags::empty()),
+        ];
+
+        for entry in &dacl_entries {
+            dacl.push(entry).unwrap();
+        }
+
+        for entry in &sacl_entries {
+            sacl.push(entry).unwrap();
+        }
+
+        SecurityDescriptor::from_parts(
+            DACL_PROTECTED,
+            Some(sid(WellKnownSid::BuiltinAdministrators)),
+            Some(sid(WellKnownSid::LocalSystem)),
+            Some(sacl),
+            Some(dacl),
+        )
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let descriptor = security_descriptor();
+        let json = serde_json::to_string(&descriptor).unwrap();
+        let parsed: SecurityDescriptor = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed.to_bytes(), descriptor.to_bytes());
+    }
+
+    #[test]
+    fn round_trips_through_bincode() {
+        let descriptor = security_descriptor();
+        let bytes = bincode::serialize(&descriptor).unwrap();
+        let parsed: SecurityDescriptor = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(parsed.to_bytes(), descriptor.to_bytes());
+
+        let entry = descriptor.dacl().unwrap().entries().nth(2).unwrap();
+        let bytes = bincode::serialize(&entry).unwrap();
+        let parsed: AccessControlEntry = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(parsed, entry.to_access_control_entry().unwrap());
+    }
+
+    #[test]
+    fn serializes_readable_entry_fields() {
+        let value = serde_json::to_value(security_descriptor()).unwrap();
+        let dacl = &value["dacl"]["entries"];
+        let sacl = &value["sacl"]["entries"];
+
+        assert_eq!(dacl[0]["trustee"], "S-1-5-11");
+        assert_eq!(dacl[0]["flags"], serde_json::json!(["CONTAINER_INHERIT"]));
+        assert_eq!(
+            dacl[1]["object_type"],
+            "bf967aba-0de6-11d0-a285-00aa003049e2"
+        );
+        assert_eq!(dacl[2]["condition"], "(@User.clearance >= 5)");
+        assert_eq!(dacl[2].get("application_data"), None);
+        assert_eq!(dacl[3]["type"], "SystemAccessFilter");
+        assert_eq!(dacl[3]["data"], "01020304");
+        assert_eq!(sacl[0]["policy"], serde_json::json!(["NO_WRITE_UP"]));
+        assert_eq!(sacl[0]["trustee"], "S-1-16-12288");
+        assert_eq!(sacl[0].get("mask"), None);
+        assert_eq!(sacl[1]["attribute"]["name"], "Secrecy");
+        assert_eq!(
+            sacl[1]["attribute"]["values"],
+            serde_json::json!([{ "String": "High" }])
+        );
+    }
+
+    #[test]
+    fn keeps_unparseable_application_data() {
+        let entry = AccessControlEntry::builder(AceType::AccessAllowedCallback)
+            .mask(AccessMask::GENERIC_READ)
+            .trustee(&sid(WellKnownSid::World))
+            .application_data(vec![0xDE, 0xAD, 0xBE, 0xEF])
+            .build()
+            .unwrap();
+        let value = serde_json::to_value(&entry).unwrap();
+
+        assert_eq!(value["application_data"], "DEADBEEF");
+        assert_eq!(value.get("condition"), None);
+        assert_eq!(
+            serde_json::from_value::<AccessControlEntry>(value).unwrap(),
+            entry
+        );
+    }
+
+    #[test]
+    fn keeps_conditions_without_a_text_form_as_data() {
+        let condition = ConditionalExpression::binary(
+            BinaryOperator::Equal,
+            ConditionalExpression::attribute(ClaimSource::User, "name"),
+            ConditionalExpression::literal(ConditionalLiteral::String("\"quoted\"".to_owned())),
+        );
+        let entry = AccessControlEntry::builder(AceType::AccessAllowedCallback)
+            .mask(AccessMask::GENERIC_READ)
+            .trustee(&sid(WellKnownSid::World))
+            .condition(&condition)
+            .build()
+            .unwrap();
+        let value = serde_json::to_value(&entry).unwrap();
+
+        assert_eq!(value.get("condition"), None);
+        assert!(value["application_data"].is_string());
+        assert_eq!(
+            serde_json::from_value::<AccessControlEntry>(value).unwrap(),
+            entry
+        );
+    }
+
+    #[test]
+    fn keeps_undefined_access_mask_bits() {
+        let entry = AccessControlEntry::builder(AceType::AccessAllowed)
+            .mask(AccessMask::from_raw(
+                AccessMask::GENERIC_READ.bits() | 0x0080_0000,
+            ))
+            .trustee(&sid(WellKnownSid::World))
+            .build()
+            .unwrap();
+        let value = serde_json::to_value(&entry).unwrap();
+
+        assert_eq!(
+            value["mask"],
+            serde_json::json!(["GENERIC_READ", "0x800000"])
+        );
+        assert_eq!(
+            serde_json::from_value::<AccessControlEntry>(value).unwrap(),
+            entry
+        );
+    }
+
+    #[test]
+    fn round_trips_null_dacl() {
+        let descriptor = SecurityDescriptor::from_parts(DACL_PRESENT, None, None, None, None);
+        let json = serde_json::to_string(&descriptor).unwrap();
+        let parsed: SecurityDescriptor = serde_json::from_str(&json).unwrap();
+
+        assert!(parsed.dacl().is_none());
+        assert_ne!(parsed.control() & DACL_PRESENT, 0);
+        assert_eq!(parsed.to_bytes(), descriptor.to_bytes());
+
+        let value: Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["dacl"], Value::Null);
+    }
+}
@@ -0,0 +1,315 @@
+use std::char;
+
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use bytes::{read_u32, write_u16};
+
+use super::{IdentifierAuthority, InvalidSecurityIdError, SecurityId, SecurityIdPtr};
+
+const SECURITY_APP_PACKAGE_BASE_RID: u32 = 2;
+const SECURITY_APP_PACKAGE_RID_COUNT: usize = 8;
+const SECURITY_PARENT_PACKAGE_RID_COUNT: usize = 7;
+const SECURITY_CHILD_PACKAGE_RID_COUNT: usize = 4;
+const SECURITY_CAPABILITY_BASE_RID: u32 = 3;
+const SECURITY_CAPABILITY_APP_RID: u32 = 1024;
+const SECURITY_BUILTIN_DOMAIN_RID: u32 = 32;
+const SECURITY_SERVICE_ID_BASE_RID: u32 = 80;
+const SECURITY_APPPOOL_ID_BASE_RID: u32 = 82;
+const SECURITY_VIRTUALSERVER_ID_BASE_RID: u32 = 83;
+const SECURITY_TASK_ID_BASE_RID: u32 = 87;
+const SECURITY_VIRTUALSERVER_ID_RID: u32 = 1;
+
+impl SecurityId {
+    pub fn app_container(app_container_name: &str) -> Self {
+        let hash = Sha256::digest(utf16_le(&fold_lower(app_container_name)));
+
+        SecurityId::from_words(
+            IdentifierAuthority::APP_PACKAGE,
+            &[SECURITY_APP_PACKAGE_BASE_RID],
+            &hash[..4 * SECURITY_PARENT_PACKAGE_RID_COUNT],
+        )
+    }
+
+    pub fn child_app_container<'a, P>(
+        parent: P,
+        child_name: &str,
+    ) -> Result<Self, InvalidSecurityIdError>
+    where
+        P: Into<SecurityIdPtr<'a>>,
+    {
+        let parent = parent.into();
+        let sub_authorities: Vec<u32> = parent.sub_authorities().collect();
+
+        if parent.identifier_authority() != IdentifierAuthority::APP_PACKAGE
+            || sub_authorities.len() != SECURITY_APP_PACKAGE_RID_COUNT
+            || sub_authorities[0] != SECURITY_APP_PACKAGE_BASE_RID
+        {
+            return Err(InvalidSecurityIdError::NotAppContainer);
+        }
+
+        let hash = Sha256::digest(utf16_le(&fold_lower(child_name)));
+
+        Ok(SecurityId::from_words(
+            IdentifierAuthority::APP_PACKAGE,
+            &sub_authorities,
+            &hash[..4 * SECURITY_CHILD_PACKAGE_RID_COUNT],
+        ))
+    }
+
+    pub fn capability(capability_name: &str) -> Self {
+        let hash = Sha256::digest(utf16_le(&fold_upper(capability_name)));
+
+        SecurityId::from_words(
+            IdentifierAuthority::APP_PACKAGE,
+            &[SECURITY_CAPABILITY_BASE_RID, SECURITY_CAPABILITY_APP_RID],
+            &hash,
+        )
+    }
+
+    pub fn capability_group(capability_name: &str) -> Self {
+        let hash = Sha256::digest(utf16_le(&fold_upper(capability_name)));
+
+        SecurityId::from_words(
+            IdentifierAuthority::NT,
+            &[SECURITY_BUILTIN_DOMAIN_RID],
+            &hash,
+        )
+    }
+
+    pub fn capability_sids(capability_name: &str) -> (Self, Self) {
+        (
+            SecurityId::capability(capability_name),
+            SecurityId::capability_group(capability_name),
+        )
+    }
+
+    pub fn service(service_name: &str) -> Self {
+        SecurityId::virtual_account(SECURITY_SERVICE_ID_BASE_RID, &fold_upper(service_name))
+    }
+
+    pub fn iis_app_pool(app_pool_name: &str) -> Self {
+        SecurityId::virtual_account(SECURITY_APPPOOL_ID_BASE_RID, &fold_lower(app_pool_name))
+    }
+
+    pub fn scheduled_task(task_name: &str) -> Self {
+        SecurityId::virtual_account(SECURITY_TASK_ID_BASE_RID, &fold_upper(task_name))
+    }
+
+    pub fn virtual_machine(virtual_machine_id: [u8; 16]) -> Self {
+        SecurityId::from_words(
+            IdentifierAuthority::NT,
+            &[
+                SECURITY_VIRTUALSERVER_ID_BASE_RID,
+                SECURITY_VIRTUALSERVER_ID_RID,
+            ],
+            &virtual_machine_id,
+        )
+    }
+
+    fn virtual_account(base_rid: u32, account_name: &str) -> Self {
+        let hash = Sha1::digest(utf16_le(account_name));
+
+        SecurityId::from_words(IdentifierAuthority::NT, &[base_rid], &hash)
+    }
+
+    fn from_words(authority: IdentifierAuthority, prefix: &[u32], words: &[u8]) -> Self {
+        let mut sub_authorities = prefix.to_vec();
+
+        sub_authorities.extend((0..words.len() / 4).filter_map(|index| read_u32(words, 4 * index)));
+
+        SecurityId::from_parts(authority, &sub_authorities)
+    }
+}
+
+fn utf16_le(name: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(2 * name.len());
+
+    for code_unit in name.encode_utf16() {
+        write_u16(&mut bytes, code_unit);
+    }
+
+    bytes
+}
+
+// Windows folds names one UTF-16 code unit at a time using simple case mappings, so characters
+// outside the BMP and multi-character mappings such as "ß" -> "SS" are left untouched. Greek
+// letters with ypogegrammeni only have multi-character full mappings in `char::to_uppercase`, so
+// their simple uppercase forms are spelled out.
+fn fold_upper(name: &str) -> String {
+    name.chars()
+        .map(|character| match character {
+            '\u{1F80}'..='\u{1F87}' | '\u{1F90}'..='\u{1F97}' | '\u{1FA0}'..='\u{1FA7}' => {
+                offset_char(character, 8)
+            }
+            '\u{1FB3}' | '\u{1FC3}' | '\u{1FF3}' => offset_char(character, 9),
+            _ => simple_mapping(character, character.to_uppercase()),
+        })
+        .collect()
+}
+
+fn fold_lower(name: &str) -> String {
+    name.chars()
+        .map(|character| match character {
+            '\u{130}' => 'i',
+            _ => simple_mapping(character, character.to_lowercase()),
+        })
+        .collect()
+}
+
+fn simple_mapping<I: ExactSizeIterator<Item = char>>(character: char, mut mapping: I) -> char {
+    if character > '\u{FFFF}' || mapping.len() != 1 {
+        return character;
+    }
+
+    mapping.next().unwrap_or(character)
+}
+
+fn offset_char(character: char, offset: u32) -> char {
+    char::from_u32(character as u32 + offset).unwrap_or(character)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sid(string: &str) -> SecurityId {
+        string.parse().expect("Invalid test security ID")
+    }
+
+    #[test]
+    fn capability_sids_match_windows() {
+        assert_eq!(
+            SecurityId::capability("registryRead"),
+            sid("S-1-15-3-1024-1065365936-1281604716-3511738428-1654721687-432734479-3232135806-4053264122-3456934681")
+        );
+        assert_eq!(
+            SecurityId::capability("lpacCom"),
+            sid("S-1-15-3-1024-2405443489-874036122-4286035555-1823921565-1746547431-2453885448-3625952902-991631256")
+        );
+        assert_eq!(
+            SecurityId::capability("LPACINSTRUMENTATION"),
+            sid("S-1-15-3-1024-3153509613-960666767-3724611135-2725662640-12138253-543910227-1950414635-4190290187")
+        );
+    }
+
+    #[test]
+    fn capability_groups_share_the_capability_hash() {
+        let (capability, group) = SecurityId::capability_sids("registryRead");
+
+        assert_eq!(capability, SecurityId::capability("registryRead"));
+        assert_eq!(
+            group,
+            sid("S-1-5-32-1065365936-1281604716-3511738428-1654721687-432734479-3232135806-4053264122-3456934681")
+        );
+    }
+
+    #[test]
+    fn app_container_sids_match_windows() {
+        let edge = sid(
+            "S-1-15-2-3624051433-2125758914-1423191267-1740899205-1073925389-3782572162-737981194",
+        );
+
+        assert_eq!(
+            SecurityId::app_container("microsoft.microsoftedge_8wekyb3d8bbwe"),
+            edge
+        );
+        assert_eq!(
+            SecurityId::app_container("Microsoft.MicrosoftEdge_8wekyb3d8bbwe"),
+            edge
+        );
+    }
+
+    #[test]
+    fn child_app_container_sids_extend_the_parent() {
+        let parent = SecurityId::app_container("microsoft.microsoftedge_8wekyb3d8bbwe");
+        let child = SecurityId::child_app_container(&parent, "Child")
+            .expect("Failed to derive child AppContainer SID");
+        let parent_sub_authorities: Vec<u32> = parent.as_sid_ptr().sub_authorities().collect();
+        let child_sub_authorities: Vec<u32> = child.as_sid_ptr().sub_authorities().collect();
+
+        assert_eq!(
+            child.identifier_authority(),
+            IdentifierAuthority::APP_PACKAGE
+        );
+        assert_eq!(
+            child_sub_authorities.len(),
+            SECURITY_APP_PACKAGE_RID_COUNT + 4
+        );
+        assert_eq!(&child_sub_authorities[..8], &parent_sub_authorities[..]);
+        assert_eq!(
+            SecurityId::child_app_container(&parent, "child").ok(),
+            Some(child)
+        );
+    }
+
+    #[test]
+    fn child_app_containers_need_an_app_container_parent() {
+        let capability = SecurityId::capability("registryRead");
+
+        match SecurityId::child_app_container(&capability, "Child") {
+            Err(InvalidSecurityIdError::NotAppContainer) => {}
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn service_sids_match_windows() {
+        assert_eq!(
+            SecurityId::service("TrustedInstaller"),
+            sid("S-1-5-80-956008885-3418522649-1831038044-1853292631-2271478464")
+        );
+        assert_eq!(
+            SecurityId::service("MSSQLSERVER"),
+            sid("S-1-5-80-3880718306-3832830129-1677859214-2598158968-1052248003")
+        );
+        assert_eq!(
+            SecurityId::service("trustedinstaller"),
+            SecurityId::trusted_installer()
+        );
+    }
+
+    #[test]
+    fn iis_app_pool_sids_match_windows() {
+        assert_eq!(
+            SecurityId::iis_app_pool("DefaultAppPool"),
+            sid("S-1-5-82-3006700770-424185619-1745488364-794895919-4004696415")
+        );
+    }
+
+    #[test]
+    fn scheduled_task_sids_share_the_service_hash() {
+        assert_eq!(
+            SecurityId::scheduled_task("TrustedInstaller"),
+            sid("S-1-5-87-956008885-3418522649-1831038044-1853292631-2271478464")
+        );
+    }
+
+    #[test]
+    fn virtual_machine_sids_split_the_id_into_words() {
+        let virtual_machine_id = [
+            0x78, 0x56, 0x34, 0x12, 0xBC, 0x9A, 0xF0, 0xDE, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC,
+            0xDE, 0xF0,
+        ];
+
+        assert_eq!(
+            SecurityId::virtual_machine(virtual_machine_id),
+            sid("S-1-5-83-1-305419896-3740310204-2018915346-4041129114")
+        );
+    }
+
+    #[test]
+    fn names_are_folded_with_simple_case_mappings() {
+        assert_eq!(fold_upper("straße"), "STRAßE");
+        assert_eq!(fold_upper("\u{1FB3}\u{1F80}"), "\u{1FBC}\u{1F88}");
+        assert_eq!(fold_upper("\u{10428}"), "\u{10428}");
+        assert_eq!(fold_lower("\u{130}STANBUL"), "istanbul");
+        assert_eq!(fold_lower("\u{10400}"), "\u{10400}");
+
+        assert_eq!(SecurityId::service("straße"), SecurityId::service("STRAßE"));
+        assert_ne!(
+            SecurityId::service("straße"),
+            SecurityId::service("STRASSE")
+        );
+    }
+}
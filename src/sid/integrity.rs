@@ -0,0 +1,123 @@
+use super::{IdentifierAuthority, SecurityId, SecurityIdPtr};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[repr(u32)]
+pub enum IntegrityLevel {
+    Untrusted = 0x0000,
+    Low = 0x1000,
+    Medium = 0x2000,
+    MediumPlus = 0x2100,
+    High = 0x3000,
+    System = 0x4000,
+    ProtectedProcess = 0x5000,
+}
+
+impl IntegrityLevel {
+    pub fn from_rid(rid: u32) -> Option<Self> {
+        match rid {
+            0x0000 => Some(IntegrityLevel::Untrusted),
+            0x1000 => Some(IntegrityLevel::Low),
+            0x2000 => Some(IntegrityLevel::Medium),
+            0x2100 => Some(IntegrityLevel::MediumPlus),
+            0x3000 => Some(IntegrityLevel::High),
+            0x4000 => Some(IntegrityLevel::System),
+            0x5000 => Some(IntegrityLevel::ProtectedProcess),
+            _ => None,
+        }
+    }
+
+    pub fn rid(self) -> u32 {
+        self as u32
+    }
+}
+
+impl From<IntegrityLevel> for SecurityId {
+    fn from(integrity_level: IntegrityLevel) -> Self {
+        SecurityId::integrity_label(integrity_level)
+    }
+}
+
+impl SecurityId {
+    pub fn integrity_label(integrity_level: IntegrityLevel) -> Self {
+        SecurityId::integrity_label_from_rid(integrity_level.rid())
+    }
+
+    pub fn integrity_label_from_rid(rid: u32) -> Self {
+        SecurityId::from_parts(IdentifierAuthority::MANDATORY_LABEL, &[rid])
+    }
+}
+
+impl<'a> SecurityIdPtr<'a> {
+    pub fn is_integrity_label(&self) -> bool {
+        self.identifier_authority() == IdentifierAuthority::MANDATORY_LABEL
+            && self.sub_authority_count() == 1
+    }
+
+    pub fn integrity_level(&self) -> Option<IntegrityLevel> {
+        if !self.is_integrity_label() {
+            return None;
+        }
+
+        self.relative_identifier()
+            .and_then(IntegrityLevel::from_rid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVELS: [(u32, IntegrityLevel); 7] = [
+        (0x0000, IntegrityLevel::Untrusted),
+        (0x1000, IntegrityLevel::Low),
+        (0x2000, IntegrityLevel::Medium),
+        (0x2100, IntegrityLevel::MediumPlus),
+        (0x3000, IntegrityLevel::High),
+        (0x4000, IntegrityLevel::System),
+        (0x5000, IntegrityLevel::ProtectedProcess),
+    ];
+
+    #[test]
+    fn maps_only_exact_rids_to_levels() {
+        for &(rid, level) in &LEVELS {
+            assert_eq!(IntegrityLevel::from_rid(rid), Some(level));
+            assert_eq!(level.rid(), rid);
+            assert_eq!(IntegrityLevel::from_rid(rid + 1), None);
+            assert_eq!(IntegrityLevel::from_rid(rid.wrapping_sub(1)), None);
+        }
+
+        assert_eq!(IntegrityLevel::from_rid(0x2080), None);
+        assert_eq!(IntegrityLevel::from_rid(0x2800), None);
+        assert_eq!(IntegrityLevel::from_rid(0x6000), None);
+    }
+
+    #[test]
+    fn orders_levels_by_rid() {
+        for pair in LEVELS.windows(2) {
+            assert!(pair[0].1 < pair[1].1);
+        }
+
+        assert!(IntegrityLevel::Medium < IntegrityLevel::MediumPlus);
+        assert!(IntegrityLevel::ProtectedProcess > IntegrityLevel::System);
+    }
+
+    #[test]
+    fn reads_levels_only_from_integrity_labels() {
+        let medium = SecurityId::integrity_label(IntegrityLevel::Medium);
+        let unknown = SecurityId::integrity_label_from_rid(0x2800);
+        let local_system: SecurityId = "S-1-5-18".parse().unwrap();
+        let nested = SecurityId::new(IdentifierAuthority::MANDATORY_LABEL, &[0x2000, 1]).unwrap();
+
+        assert_eq!(medium.to_string(), "S-1-16-8192");
+        assert_eq!(
+            medium.as_sid_ptr().integrity_level(),
+            Some(IntegrityLevel::Medium)
+        );
+        assert!(unknown.as_sid_ptr().is_integrity_label());
+        assert_eq!(unknown.as_sid_ptr().integrity_level(), None);
+        assert!(!local_system.as_sid_ptr().is_integrity_label());
+        assert_eq!(local_system.as_sid_ptr().integrity_level(), None);
+        assert!(!nested.as_sid_ptr().is_integrity_label());
+        assert_eq!(nested.as_sid_ptr().integrity_level(), None);
+    }
+}
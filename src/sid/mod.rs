@@ -0,0 +1,770 @@
+mod derive;
+mod integrity;
+mod parse;
+mod resolver;
+mod trust;
+mod well_known;
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+#[cfg(windows)]
+use std::slice;
+
+#[cfg(windows)]
+use winapi::um::winnt::PSID;
+
+use bytes::{read_u32, write_u32};
+
+pub use self::integrity::IntegrityLevel;
+pub use self::parse::ParseSecurityIdError;
+pub use self::resolver::{
+    AccountName, DuplicateAccountNameError, LoadSidDatabaseError, ResolvedSecurityId, SidDatabase,
+    SidResolver, WellKnownSidResolver,
+};
+pub use self::trust::{ProtectionLevel, ProtectionType, TrustLabel};
+pub use self::well_known::WellKnownSid;
+
+const SID_REVISION: u8 = 1;
+const SID_MAX_SUB_AUTHORITIES: usize = 15;
+const SID_HEADER_SIZE: usize = 8;
+const SID_AUTHORITY_OFFSET: usize = 2;
+
+const TRUSTED_INSTALLER_SUB_AUTHORITIES: [u32; 6] = [
+    80, 956008885, 3418522649, 1831038044, 1853292631, 2271478464,
+];
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct IdentifierAuthority([u8; 6]);
+
+impl IdentifierAuthority {
+    pub const NULL: Self = IdentifierAuthority([0, 0, 0, 0, 0, 0]);
+    pub const WORLD: Self = IdentifierAuthority([0, 0, 0, 0, 0, 1]);
+    pub const LOCAL: Self = IdentifierAuthority([0, 0, 0, 0, 0, 2]);
+    pub const CREATOR: Self = IdentifierAuthority([0, 0, 0, 0, 0, 3]);
+    pub const NON_UNIQUE: Self = IdentifierAuthority([0, 0, 0, 0, 0, 4]);
+    pub const NT: Self = IdentifierAuthority([0, 0, 0, 0, 0, 5]);
+    pub const RESOURCE_MANAGER: Self = IdentifierAuthority([0, 0, 0, 0, 0, 9]);
+    pub const APP_PACKAGE: Self = IdentifierAuthority([0, 0, 0, 0, 0, 15]);
+    pub const MANDATORY_LABEL: Self = IdentifierAuthority([0, 0, 0, 0, 0, 16]);
+    pub const SCOPED_POLICY_ID: Self = IdentifierAuthority([0, 0, 0, 0, 0, 17]);
+    pub const AUTHENTICATION: Self = IdentifierAuthority([0, 0, 0, 0, 0, 18]);
+    pub const PROCESS_TRUST: Self = IdentifierAuthority([0, 0, 0, 0, 0, 19]);
+
+    pub fn from_bytes(bytes: [u8; 6]) -> Self {
+        IdentifierAuthority(bytes)
+    }
+
+    pub fn from_value(value: u64) -> Option<Self> {
+        if value >> 48 != 0 {
+            return None;
+        }
+
+        let mut bytes = [0; 6];
+
+        for (index, byte) in bytes.iter_mut().enumerate() {
+            *byte = (value >> (8 * (5 - index))) as u8;
+        }
+
+        Some(IdentifierAuthority(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 6] {
+        &self.0
+    }
+
+    pub fn value(&self) -> u64 {
+        self.0
+            .iter()
+            .fold(0, |value, &byte| value << 8 | u64::from(byte))
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SecurityIdPtr<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> SecurityIdPtr<'a> {
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, InvalidSecurityIdError> {
+        let sid = Self::validate_prefix(bytes)?;
+
+        if sid.bytes.len() != bytes.len() {
+            return Err(InvalidSecurityIdError::TrailingBytes {
+                expected: sid.bytes.len(),
+                actual: bytes.len(),
+            });
+        }
+
+        Ok(sid)
+    }
+
+    pub(crate) fn from_prefix(bytes: &'a [u8]) -> Option<Self> {
+        Self::validate_prefix(bytes).ok()
+    }
+
+    fn validate_prefix(bytes: &'a [u8]) -> Result<Self, InvalidSecurityIdError> {
+        if bytes.len() < SID_HEADER_SIZE {
+            return Err(InvalidSecurityIdError::Truncated {
+                expected: SID_HEADER_SIZE,
+                actual: bytes.len(),
+            });
+        }
+
+        let revision = bytes[0];
+        let sub_authority_count = bytes[1] as usize;
+        let size = SID_HEADER_SIZE + 4 * sub_authority_count;
+
+        if revision != SID_REVISION {
+            return Err(InvalidSecurityIdError::UnsupportedRevision(revision));
+        }
+
+        if sub_authority_count > SID_MAX_SUB_AUTHORITIES {
+            return Err(InvalidSecurityIdError::TooManySubAuthorities(
+                sub_authority_count,
+            ));
+        }
+
+        match bytes.get(..size) {
+            Some(bytes) => Ok(SecurityIdPtr { bytes }),
+            None => Err(InvalidSecurityIdError::Truncated {
+                expected: size,
+                actual: bytes.len(),
+            }),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `sid` must point to a valid security ID that outlives the returned value.
+    #[cfg(windows)]
+    pub unsafe fn from_ptr(sid: PSID) -> Self {
+        let sid_bytes = sid as *const u8;
+        let sub_authority_count = *sid_bytes.offset(1) as usize;
+        let size = SID_HEADER_SIZE + 4 * sub_authority_count;
+
+        SecurityIdPtr {
+            bytes: slice::from_raw_parts(sid_bytes, size),
+        }
+    }
+
+    pub fn is_builtin_administrators(&self) -> bool {
+        self.is_well_known(WellKnownSid::BuiltinAdministrators)
+    }
+
+    pub fn is_local_system(&self) -> bool {
+        self.is_well_known(WellKnownSid::LocalSystem)
+    }
+
+    pub fn is_logon_session(&self) -> bool {
+        self.is_well_known(WellKnownSid::LogonIds)
+    }
+
+    pub fn logon_session_id(&self) -> Option<(u32, u32)> {
+        if !self.is_logon_session() {
+            return None;
+        }
+
+        Some((self.sub_authority(1)?, self.sub_authority(2)?))
+    }
+
+    pub fn is_owner_rights(&self) -> bool {
+        self.is_well_known(WellKnownSid::CreatorOwnerRights)
+    }
+
+    pub fn is_creator_owner_server(&self) -> bool {
+        self.is_well_known(WellKnownSid::CreatorOwnerServer)
+    }
+
+    pub fn is_trusted_installer(&self) -> bool {
+        self.matches(IdentifierAuthority::NT, &TRUSTED_INSTALLER_SUB_AUTHORITIES)
+    }
+
+    pub fn is_local_account(&self) -> bool {
+        self.is_well_known(WellKnownSid::LocalAccount)
+    }
+
+    pub fn is_local_account_and_administrator(&self) -> bool {
+        self.is_well_known(WellKnownSid::LocalAccountAndAdministrator)
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    #[cfg(windows)]
+    pub fn as_ptr(&self) -> PSID {
+        self.bytes.as_ptr() as PSID
+    }
+
+    pub fn to_security_id(self) -> SecurityId {
+        SecurityId {
+            bytes: self.bytes.to_vec(),
+        }
+    }
+
+    pub fn revision(&self) -> u8 {
+        self.bytes[0]
+    }
+
+    pub fn identifier_authority(&self) -> IdentifierAuthority {
+        let mut authority = [0; 6];
+
+        authority.copy_from_slice(&self.bytes[SID_AUTHORITY_OFFSET..SID_HEADER_SIZE]);
+
+        IdentifierAuthority(authority)
+    }
+
+    pub fn sub_authority_count(&self) -> usize {
+        self.bytes[1] as usize
+    }
+
+    pub fn sub_authority(&self, index: usize) -> Option<u32> {
+        read_u32(self.bytes, SID_HEADER_SIZE + 4 * index)
+    }
+
+    pub fn sub_authorities(&self) -> SubAuthorities<'a> {
+        SubAuthorities {
+            bytes: &self.bytes[SID_HEADER_SIZE..],
+        }
+    }
+
+    pub fn relative_identifier(&self) -> Option<u32> {
+        self.sub_authorities().last()
+    }
+
+    pub fn domain(&self) -> Option<SecurityId> {
+        let sub_authority_count = self.sub_authority_count().checked_sub(1)?;
+        let mut bytes = self.bytes[..SID_HEADER_SIZE + 4 * sub_authority_count].to_vec();
+
+        bytes[1] = sub_authority_count as u8;
+
+        Some(SecurityId { bytes })
+    }
+
+    pub fn is_in_domain<'b, D>(&self, domain: D) -> bool
+    where
+        D: Into<SecurityIdPtr<'b>>,
+    {
+        let domain = domain.into();
+
+        self.sub_authority_count() == domain.sub_authority_count() + 1
+            && self.identifier_authority() == domain.identifier_authority()
+            && self.bytes[SID_HEADER_SIZE..domain.bytes.len()] == domain.bytes[SID_HEADER_SIZE..]
+    }
+
+    pub fn with_rid(&self, rid: u32) -> Result<SecurityId, InvalidSecurityIdError> {
+        if self.sub_authority_count() >= SID_MAX_SUB_AUTHORITIES {
+            return Err(InvalidSecurityIdError::TooManySubAuthorities(
+                self.sub_authority_count() + 1,
+            ));
+        }
+
+        let mut bytes = Vec::with_capacity(self.bytes.len() + 4);
+
+        bytes.extend_from_slice(self.bytes);
+        bytes[1] += 1;
+        write_u32(&mut bytes, rid);
+
+        Ok(SecurityId { bytes })
+    }
+
+    fn matches(&self, authority: IdentifierAuthority, sub_authorities: &[u32]) -> bool {
+        let mut expected_bytes = Vec::with_capacity(self.bytes.len());
+
+        encode(&mut expected_bytes, authority, sub_authorities);
+
+        self.bytes == &expected_bytes[..]
+    }
+}
+
+impl<'a> Display for SecurityIdPtr<'a> {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        let authority = self.identifier_authority().value();
+
+        write!(formatter, "S-{}-", self.bytes[0])?;
+
+        if authority >> 32 == 0 {
+            write!(formatter, "{}", authority)?;
+        } else {
+            write!(formatter, "0x{:012X}", authority)?;
+        }
+
+        for sub_authority in self.sub_authorities() {
+            write!(formatter, "-{}", sub_authority)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Debug for SecurityIdPtr<'a> {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        Display::fmt(self, formatter)
+    }
+}
+
+impl<'a, 'b> PartialEq<SecurityIdPtr<'b>> for SecurityIdPtr<'a> {
+    fn eq(&self, other: &SecurityIdPtr<'b>) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl<'a> Eq for SecurityIdPtr<'a> {}
+
+impl<'a, 'b> PartialOrd<SecurityIdPtr<'b>> for SecurityIdPtr<'a> {
+    fn partial_cmp(&self, other: &SecurityIdPtr<'b>) -> Option<Ordering> {
+        Some(self.bytes.cmp(other.bytes))
+    }
+}
+
+impl<'a> Ord for SecurityIdPtr<'a> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.bytes.cmp(other.bytes)
+    }
+}
+
+impl<'a> Hash for SecurityIdPtr<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bytes.hash(state)
+    }
+}
+
+impl<'a> AsRef<SecurityIdPtr<'a>> for SecurityIdPtr<'a> {
+    fn as_ref(&self) -> &SecurityIdPtr<'a> {
+        self
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum InvalidSecurityIdError {
+    #[fail(display = "Unsupported security ID revision: {}", _0)]
+    UnsupportedRevision(u8),
+
+    #[fail(
+        display = "Security ID has {} sub-authorities, but at most 15 are allowed",
+        _0
+    )]
+    TooManySubAuthorities(usize),
+
+    #[fail(
+        display = "Security ID is truncated: it needs {} bytes, but only {} are available",
+        expected, actual
+    )]
+    Truncated { expected: usize, actual: usize },
+
+    #[fail(
+        display = "Security ID is followed by trailing bytes: it needs {} bytes, but {} were given",
+        expected, actual
+    )]
+    TrailingBytes { expected: usize, actual: usize },
+
+    #[fail(display = "Well-known security ID {:?} requires a domain", _0)]
+    DomainRelative(WellKnownSid),
+
+    #[fail(display = "Logon session security IDs require a logon session ID")]
+    LogonSession,
+
+    #[fail(display = "Security ID is not an AppContainer package SID")]
+    NotAppContainer,
+
+    #[fail(display = "Security ID is not an account domain SID")]
+    NotDomain,
+}
+
+/// An owned security ID.
+///
+/// `SecurityId` no longer dereferences to `SecurityIdPtr`: the view borrows the owned buffer, so
+/// it cannot be handed out with a `'static` lifetime. The common predicates and accessors are
+/// forwarded below, the rest of the read-only API is available through `as_sid_ptr`, and hashed
+/// collections of owned IDs can be queried with `SecurityIdPtr::as_bytes`.
+#[derive(Clone)]
+pub struct SecurityId {
+    bytes: Vec<u8>,
+}
+
+macro_rules! forward_to_sid_ptr {
+    ($( $method:ident($( $argument:ident: $argument_type:ty ),*) -> $output:ty; )*) => {
+        impl SecurityId {
+            $(
+                pub fn $method(&self, $( $argument: $argument_type ),*) -> $output {
+                    self.as_sid_ptr().$method($( $argument ),*)
+                }
+            )*
+        }
+    };
+}
+
+forward_to_sid_ptr! {
+    is_builtin_administrators() -> bool;
+    is_local_system() -> bool;
+    is_logon_session() -> bool;
+    logon_session_id() -> Option<(u32, u32)>;
+    is_owner_rights() -> bool;
+    is_creator_owner_server() -> bool;
+    is_trusted_installer() -> bool;
+    is_local_account() -> bool;
+    is_local_account_and_administrator() -> bool;
+    is_well_known(well_known_sid: WellKnownSid) -> bool;
+    is_integrity_label() -> bool;
+    integrity_level() -> Option<IntegrityLevel>;
+    is_trust_label() -> bool;
+    revision() -> u8;
+    identifier_authority() -> IdentifierAuthority;
+    sub_authority_count() -> usize;
+    sub_authority(index: usize) -> Option<u32>;
+    relative_identifier() -> Option<u32>;
+    domain() -> Option<SecurityId>;
+    with_rid(rid: u32) -> Result<SecurityId, InvalidSecurityIdError>;
+}
+
+impl SecurityId {
+    pub fn builtin_administrators() -> Result<Self, InvalidSecurityIdError> {
+        Self::well_known(WellKnownSid::BuiltinAdministrators)
+    }
+
+    pub fn logon_session(high: u32, low: u32) -> Self {
+        SecurityId::from_parts(IdentifierAuthority::NT, &[5, high, low])
+    }
+
+    pub fn owner_rights() -> Self {
+        SecurityId::from_parts(IdentifierAuthority::CREATOR, &[4])
+    }
+
+    pub fn creator_owner_server() -> Self {
+        SecurityId::from_parts(IdentifierAuthority::CREATOR, &[2])
+    }
+
+    pub fn trusted_installer() -> Self {
+        SecurityId::from_parts(IdentifierAuthority::NT, &TRUSTED_INSTALLER_SUB_AUTHORITIES)
+    }
+
+    pub fn local_account() -> Self {
+        SecurityId::from_parts(IdentifierAuthority::NT, &[113])
+    }
+
+    pub fn local_account_and_administrator() -> Self {
+        SecurityId::from_parts(IdentifierAuthority::NT, &[114])
+    }
+
+    pub fn new(
+        authority: IdentifierAuthority,
+        sub_authorities: &[u32],
+    ) -> Result<Self, InvalidSecurityIdError> {
+        if sub_authorities.len() > SID_MAX_SUB_AUTHORITIES {
+            return Err(InvalidSecurityIdError::TooManySubAuthorities(
+                sub_authorities.len(),
+            ));
+        }
+
+        Ok(SecurityId::from_parts(authority, sub_authorities))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InvalidSecurityIdError> {
+        SecurityIdPtr::from_bytes(bytes).map(SecurityIdPtr::to_security_id)
+    }
+
+    fn from_parts(authority: IdentifierAuthority, sub_authorities: &[u32]) -> Self {
+        let mut bytes = Vec::with_capacity(SID_HEADER_SIZE + 4 * sub_authorities.len());
+
+        encode(&mut bytes, authority, sub_authorities);
+
+        SecurityId { bytes }
+    }
+
+    pub fn as_sid_ptr<'a>(&'a self) -> SecurityIdPtr<'a> {
+        SecurityIdPtr { bytes: &self.bytes }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl<'a> From<&'a SecurityId> for SecurityIdPtr<'a> {
+    fn from(sid: &'a SecurityId) -> Self {
+        sid.as_sid_ptr()
+    }
+}
+
+impl PartialEq for SecurityId {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl<'a> PartialEq<SecurityIdPtr<'a>> for SecurityId {
+    fn eq(&self, other: &SecurityIdPtr<'a>) -> bool {
+        self.as_sid_ptr() == *other
+    }
+}
+
+impl<'a> PartialEq<SecurityId> for SecurityIdPtr<'a> {
+    fn eq(&self, other: &SecurityId) -> bool {
+        *self == other.as_sid_ptr()
+    }
+}
+
+impl Eq for SecurityId {}
+
+impl PartialOrd for SecurityId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a> PartialOrd<SecurityIdPtr<'a>> for SecurityId {
+    fn partial_cmp(&self, other: &SecurityIdPtr<'a>) -> Option<Ordering> {
+        self.as_sid_ptr().partial_cmp(other)
+    }
+}
+
+impl<'a> PartialOrd<SecurityId> for SecurityIdPtr<'a> {
+    fn partial_cmp(&self, other: &SecurityId) -> Option<Ordering> {
+        self.partial_cmp(&other.as_sid_ptr())
+    }
+}
+
+impl Ord for SecurityId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.bytes.cmp(&other.bytes)
+    }
+}
+
+impl Hash for SecurityId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_sid_ptr().hash(state)
+    }
+}
+
+impl Borrow<[u8]> for SecurityId {
+    fn borrow(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl AsRef<[u8]> for SecurityId {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Display for SecurityId {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.as_sid_ptr(), formatter)
+    }
+}
+
+impl Debug for SecurityId {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.as_sid_ptr(), formatter)
+    }
+}
+
+pub struct SubAuthorities<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for SubAuthorities<'a> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sub_authority = read_u32(self.bytes, 0)?;
+
+        self.bytes = &self.bytes[4..];
+
+        Some(sub_authority)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.bytes.len() / 4;
+
+        (count, Some(count))
+    }
+}
+
+impl<'a> ExactSizeIterator for SubAuthorities<'a> {}
+
+fn encode(buffer: &mut Vec<u8>, authority: IdentifierAuthority, sub_authorities: &[u32]) {
+    buffer.push(SID_REVISION);
+    buffer.push(sub_authorities.len() as u8);
+    buffer.extend_from_slice(authority.as_bytes());
+
+    for &sub_authority in sub_authorities {
+        write_u32(buffer, sub_authority);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::{BTreeSet, HashSet};
+
+    const ADMINISTRATOR: [u8; 28] = [
+        1, 5, 0, 0, 0, 0, 0, 5, 21, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 0xF4, 1, 0, 0,
+    ];
+
+    #[test]
+    fn reads_binary_security_ids() {
+        let sid = SecurityIdPtr::from_bytes(&ADMINISTRATOR).unwrap();
+
+        assert_eq!(sid.to_string(), "S-1-5-21-1-2-3-500");
+        assert_eq!(sid.revision(), 1);
+        assert_eq!(sid.identifier_authority(), IdentifierAuthority::NT);
+        assert_eq!(sid.sub_authority_count(), 5);
+        assert_eq!(sid.sub_authority(1), Some(1));
+        assert_eq!(sid.sub_authority(5), None);
+        assert_eq!(
+            sid.sub_authorities().collect::<Vec<_>>(),
+            vec![21, 1, 2, 3, 500]
+        );
+        assert_eq!(sid.relative_identifier(), Some(500));
+        assert_eq!(SecurityId::from_bytes(&ADMINISTRATOR).unwrap(), sid);
+    }
+
+    #[test]
+    fn owned_and_borrowed_security_ids_agree() {
+        let administrator = SecurityId::from_bytes(&ADMINISTRATOR).unwrap();
+        let domain = administrator.domain().unwrap();
+        let guest = domain.with_rid(501).unwrap();
+        let hashed: HashSet<_> = vec![administrator.clone(), domain.clone()]
+            .into_iter()
+            .collect();
+        let sorted: BTreeSet<_> = hashed.iter().cloned().collect();
+
+        assert!(hashed.contains(&ADMINISTRATOR[..]));
+        assert!(hashed.contains(administrator.as_sid_ptr().as_bytes()));
+        assert!(!hashed.contains(guest.as_sid_ptr().as_bytes()));
+        assert!(sorted.contains(&ADMINISTRATOR[..]));
+        assert!(!sorted.contains(guest.as_sid_ptr().as_bytes()));
+        assert_eq!(hash(&administrator), hash(&administrator.as_sid_ptr()));
+        assert_eq!(hash(&administrator), hash(&ADMINISTRATOR[..]));
+
+        let ids = [&domain, &administrator, &guest];
+
+        for &left in &ids {
+            for &right in &ids {
+                let expected = left.as_sid_ptr().cmp(&right.as_sid_ptr());
+
+                assert_eq!(left.cmp(right), expected);
+                assert_eq!(left.partial_cmp(&right.as_sid_ptr()), Some(expected));
+                assert_eq!(left.as_sid_ptr().partial_cmp(right), Some(expected));
+                assert_eq!(*left == right.as_sid_ptr(), expected == Ordering::Equal);
+            }
+        }
+    }
+
+    type Predicate = fn(&SecurityId) -> bool;
+
+    #[test]
+    fn recognises_special_identities() {
+        let predicates: [(&str, Predicate); 5] = [
+            ("S-1-3-4", SecurityId::is_owner_rights),
+            ("S-1-3-2", SecurityId::is_creator_owner_server),
+            (
+                "S-1-5-80-956008885-3418522649-1831038044-1853292631-2271478464",
+                SecurityId::is_trusted_installer,
+            ),
+            ("S-1-5-113", SecurityId::is_local_account),
+            ("S-1-5-114", SecurityId::is_local_account_and_administrator),
+        ];
+        let near_misses = [
+            "S-1-3-3",
+            "S-1-5-4",
+            "S-1-3-4-1",
+            "S-1-5-80-956008885-3418522649-1831038044-1853292631-2271478465",
+            "S-1-5-80-956008885-3418522649-1831038044-1853292631",
+            "S-1-5-115",
+            "S-1-5-113-1",
+            "S-1-16-113",
+        ];
+
+        for &(expected, _) in &predicates {
+            let sid: SecurityId = expected.parse().unwrap();
+
+            for &(name, predicate) in &predicates {
+                assert_eq!(predicate(&sid), name == expected, "{} for {}", name, sid);
+            }
+        }
+
+        for near_miss in &near_misses {
+            let sid: SecurityId = near_miss.parse().unwrap();
+
+            for &(name, predicate) in &predicates {
+                assert!(!predicate(&sid), "{} for {}", name, sid);
+            }
+        }
+    }
+
+    fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn splits_and_joins_domain_relative_ids() {
+        let sid = SecurityIdPtr::from_bytes(&ADMINISTRATOR).unwrap();
+        let domain = sid.domain().unwrap();
+
+        assert_eq!(domain.to_string(), "S-1-5-21-1-2-3");
+        assert!(sid.is_in_domain(&domain));
+        assert!(!domain.as_sid_ptr().is_in_domain(&domain));
+        assert_eq!(domain.with_rid(500).unwrap(), sid);
+
+        let full = SecurityId::new(IdentifierAuthority::NT, &[0; 15]).unwrap();
+
+        match full.with_rid(1) {
+            Err(InvalidSecurityIdError::TooManySubAuthorities(16)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_binary_security_ids() {
+        let mut wrong_revision = ADMINISTRATOR;
+        let mut too_many = ADMINISTRATOR;
+        let mut trailing = ADMINISTRATOR.to_vec();
+
+        wrong_revision[0] = 2;
+        too_many[1] = 16;
+        trailing.push(0);
+
+        match SecurityIdPtr::from_bytes(&wrong_revision) {
+            Err(InvalidSecurityIdError::UnsupportedRevision(2)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match SecurityIdPtr::from_bytes(&too_many) {
+            Err(InvalidSecurityIdError::TooManySubAuthorities(16)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match SecurityIdPtr::from_bytes(&ADMINISTRATOR[..7]) {
+            Err(InvalidSecurityIdError::Truncated {
+                expected: 8,
+                actual: 7,
+            }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match SecurityIdPtr::from_bytes(&ADMINISTRATOR[..24]) {
+            Err(InvalidSecurityIdError::Truncated {
+                expected: 28,
+                actual: 24,
+            }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match SecurityIdPtr::from_bytes(&trailing) {
+            Err(InvalidSecurityIdError::TrailingBytes {
+                expected: 28,
+                actual: 29,
+            }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(
+            SecurityIdPtr::from_prefix(&trailing).map(|sid| sid.to_string()),
+            Some("S-1-5-21-1-2-3-500".to_owned())
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use std::str::FromStr;
+
+use super::{IdentifierAuthority, SecurityId, SID_REVISION};
+
+#[derive(Debug, Fail)]
+pub enum ParseSecurityIdError {
+    #[fail(display = "Security ID string does not start with \"S-\"")]
+    MissingPrefix,
+
+    #[fail(display = "Unsupported security ID revision: {:?}", _0)]
+    InvalidRevision(String),
+
+    #[fail(display = "Security ID string is missing the identifier authority")]
+    MissingAuthority,
+
+    #[fail(display = "Invalid identifier authority: {:?}", _0)]
+    InvalidAuthority(String),
+
+    #[fail(display = "Invalid sub-authority at position {}: {:?}", index, value)]
+    InvalidSubAuthority { index: usize, value: String },
+
+    #[fail(
+        display = "Security ID has {} sub-authorities, but at most 15 are allowed",
+        _0
+    )]
+    TooManySubAuthorities(usize),
+}
+
+impl FromStr for SecurityId {
+    type Err = ParseSecurityIdError;
+
+    fn from_str(sid_string: &str) -> Result<Self, Self::Err> {
+        let mut parts = sid_string.split('-');
+
+        match parts.next() {
+            Some("S") | Some("s") => {}
+            _ => return Err(ParseSecurityIdError::MissingPrefix),
+        }
+
+        let revision = parts.next().ok_or(ParseSecurityIdError::MissingPrefix)?;
+
+        if parse_decimal(revision) != Some(u64::from(SID_REVISION)) {
+            return Err(ParseSecurityIdError::InvalidRevision(revision.to_owned()));
+        }
+
+        let authority = parts.next().ok_or(ParseSecurityIdError::MissingAuthority)?;
+        let identifier_authority = parse_authority(authority)
+            .ok_or_else(|| ParseSecurityIdError::InvalidAuthority(authority.to_owned()))?;
+
+        let sub_authorities = parts
+            .enumerate()
+            .map(|(index, sub_authority)| {
+                parse_decimal(sub_authority)
+                    .filter(|&value| value <= u64::from(u32::MAX))
+                    .map(|value| value as u32)
+                    .ok_or_else(|| ParseSecurityIdError::InvalidSubAuthority {
+                        index,
+                        value: sub_authority.to_owned(),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        SecurityId::new(identifier_authority, &sub_authorities)
+            .map_err(|_| ParseSecurityIdError::TooManySubAuthorities(sub_authorities.len()))
+    }
+}
+
+fn parse_authority(authority: &str) -> Option<IdentifierAuthority> {
+    let value = if authority.starts_with("0x") || authority.starts_with("0X") {
+        parse_hexadecimal(&authority[2..])?
+    } else {
+        parse_decimal(authority).filter(|&value| value <= u64::from(u32::MAX))?
+    };
+
+    IdentifierAuthority::from_value(value)
+}
+
+fn parse_decimal(digits: &str) -> Option<u64> {
+    if digits.is_empty() || !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+        return None;
+    }
+
+    digits.parse().ok()
+}
+
+fn parse_hexadecimal(digits: &str) -> Option<u64> {
+    if digits.is_empty()
+        || digits.len() > 12
+        || !digits.bytes().all(|digit| digit.is_ascii_hexdigit())
+    {
+        return None;
+    }
+
+    u64::from_str_radix(digits, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(sid_string: &str) -> Result<SecurityId, ParseSecurityIdError> {
+        sid_string.parse()
+    }
+
+    #[test]
+    fn parses_sid_strings() {
+        let administrators = parse("S-1-5-32-544").unwrap();
+
+        assert_eq!(
+            administrators.as_sid_ptr().as_bytes(),
+            &[1, 2, 0, 0, 0, 0, 0, 5, 32, 0, 0, 0, 32, 2, 0, 0]
+        );
+        assert_eq!(administrators.to_string(), "S-1-5-32-544");
+        assert_eq!(parse("s-1-5-18").unwrap().to_string(), "S-1-5-18");
+        assert_eq!(parse("S-1-0").unwrap().sub_authority_count(), 0);
+    }
+
+    #[test]
+    fn formats_large_identifier_authorities_in_hexadecimal() {
+        let sid = parse("S-1-0x123456789ABC-7").unwrap();
+
+        assert_eq!(
+            sid.identifier_authority(),
+            IdentifierAuthority::from_bytes([0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC])
+        );
+        assert_eq!(sid.to_string(), "S-1-0x123456789ABC-7");
+        assert_eq!(parse("S-1-0x10-1").unwrap().to_string(), "S-1-16-1");
+    }
+
+    #[test]
+    fn rejects_malformed_sid_strings() {
+        match parse("X-1-5-18") {
+            Err(ParseSecurityIdError::MissingPrefix) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match parse("S-2-5-18") {
+            Err(ParseSecurityIdError::InvalidRevision(ref revision)) if revision == "2" => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match parse("S-1") {
+            Err(ParseSecurityIdError::MissingAuthority) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match parse("S-1-4294967296-1") {
+            Err(ParseSecurityIdError::InvalidAuthority(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match parse("S-1-5-21-+1") {
+            Err(ParseSecurityIdError::InvalidSubAuthority { index: 1, .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match parse("S-1-5-4294967296") {
+            Err(ParseSecurityIdError::InvalidSubAuthority { index: 0, .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match parse("S-1-5-") {
+            Err(ParseSecurityIdError::InvalidSubAuthority { index: 0, .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match parse(&format!("S-1-5{}", "-1".repeat(16))) {
+            Err(ParseSecurityIdError::TooManySubAuthorities(16)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,662 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use super::{IdentifierAuthority, SecurityId, SecurityIdPtr, TRUSTED_INSTALLER_SUB_AUTHORITIES};
+
+const NULL: IdentifierAuthority = IdentifierAuthority::NULL;
+const WORLD: IdentifierAuthority = IdentifierAuthority::WORLD;
+const LOCAL: IdentifierAuthority = IdentifierAuthority::LOCAL;
+const CREATOR: IdentifierAuthority = IdentifierAuthority::CREATOR;
+const NT: IdentifierAuthority = IdentifierAuthority::NT;
+const APP_PACKAGE: IdentifierAuthority = IdentifierAuthority::APP_PACKAGE;
+const MANDATORY_LABEL: IdentifierAuthority = IdentifierAuthority::MANDATORY_LABEL;
+const AUTHENTICATION: IdentifierAuthority = IdentifierAuthority::AUTHENTICATION;
+
+const NT_AUTHORITY: &str = "NT AUTHORITY";
+const BUILTIN: &str = "BUILTIN";
+const APPLICATION_PACKAGE_AUTHORITY: &str = "APPLICATION PACKAGE AUTHORITY";
+const MANDATORY_LABEL_DOMAIN: &str = "Mandatory Label";
+const NT_SERVICE: &str = "NT SERVICE";
+const IIS_APPPOOL: &str = "IIS APPPOOL";
+const NT_TASK: &str = "NT TASK";
+const LOGON_SESSION_PREFIX: &str = "LogonSessionId_";
+
+const WELL_KNOWN_NAMES: &[(IdentifierAuthority, &[u32], &str, &str)] = &[
+    (NULL, &[0], "", "NULL SID"),
+    (WORLD, &[0], "", "Everyone"),
+    (LOCAL, &[0], "", "LOCAL"),
+    (LOCAL, &[1], "", "CONSOLE LOGON"),
+    (CREATOR, &[0], "", "CREATOR OWNER"),
+    (CREATOR, &[1], "", "CREATOR GROUP"),
+    (CREATOR, &[2], "", "CREATOR OWNER SERVER"),
+    (CREATOR, &[3], "", "CREATOR GROUP SERVER"),
+    (CREATOR, &[4], "", "OWNER RIGHTS"),
+    (NT, &[], "", NT_AUTHORITY),
+    (NT, &[1], NT_AUTHORITY, "DIALUP"),
+    (NT, &[2], NT_AUTHORITY, "NETWORK"),
+    (NT, &[3], NT_AUTHORITY, "BATCH"),
+    (NT, &[4], NT_AUTHORITY, "INTERACTIVE"),
+    (NT, &[6], NT_AUTHORITY, "SERVICE"),
+    (NT, &[7], NT_AUTHORITY, "ANONYMOUS LOGON"),
+    (NT, &[8], NT_AUTHORITY, "PROXY"),
+    (NT, &[9], NT_AUTHORITY, "ENTERPRISE DOMAIN CONTROLLERS"),
+    (NT, &[10], NT_AUTHORITY, "SELF"),
+    (NT, &[11], NT_AUTHORITY, "Authenticated Users"),
+    (NT, &[12], NT_AUTHORITY, "RESTRICTED"),
+    (NT, &[13], NT_AUTHORITY, "TERMINAL SERVER USER"),
+    (NT, &[14], NT_AUTHORITY, "REMOTE INTERACTIVE LOGON"),
+    (NT, &[15], NT_AUTHORITY, "This Organization"),
+    (NT, &[17], NT_AUTHORITY, "IUSR"),
+    (NT, &[18], NT_AUTHORITY, "SYSTEM"),
+    (NT, &[19], NT_AUTHORITY, "LOCAL SERVICE"),
+    (NT, &[20], NT_AUTHORITY, "NETWORK SERVICE"),
+    (
+        NT,
+        &[22],
+        NT_AUTHORITY,
+        "ENTERPRISE READ-ONLY DOMAIN CONTROLLERS BETA",
+    ),
+    (NT, &[33], NT_AUTHORITY, "WRITE RESTRICTED"),
+    (NT, &[64, 10], NT_AUTHORITY, "NTLM Authentication"),
+    (NT, &[64, 14], NT_AUTHORITY, "SChannel Authentication"),
+    (NT, &[64, 21], NT_AUTHORITY, "Digest Authentication"),
+    (NT, &[65, 1], NT_AUTHORITY, "This Organization Certificate"),
+    (NT, &[80, 0], NT_SERVICE, "ALL SERVICES"),
+    (
+        NT,
+        &TRUSTED_INSTALLER_SUB_AUTHORITIES,
+        NT_SERVICE,
+        "TrustedInstaller",
+    ),
+    (NT, &[84, 0, 0, 0, 0, 0], NT_AUTHORITY, "USER MODE DRIVERS"),
+    (NT, &[113], NT_AUTHORITY, "Local account"),
+    (
+        NT,
+        &[114],
+        NT_AUTHORITY,
+        "Local account and member of Administrators group",
+    ),
+    (NT, &[1000], NT_AUTHORITY, "Other Organization"),
+    (NT, &[32], BUILTIN, BUILTIN),
+    (NT, &[32, 544], BUILTIN, "Administrators"),
+    (NT, &[32, 545], BUILTIN, "Users"),
+    (NT, &[32, 546], BUILTIN, "Guests"),
+    (NT, &[32, 547], BUILTIN, "Power Users"),
+    (NT, &[32, 548], BUILTIN, "Account Operators"),
+    (NT, &[32, 549], BUILTIN, "Server Operators"),
+    (NT, &[32, 550], BUILTIN, "Print Operators"),
+    (NT, &[32, 551], BUILTIN, "Backup Operators"),
+    (NT, &[32, 552], BUILTIN, "Replicator"),
+    (
+        NT,
+        &[32, 554],
+        BUILTIN,
+        "Pre-Windows 2000 Compatible Access",
+    ),
+    (NT, &[32, 555], BUILTIN, "Remote Desktop Users"),
+    (NT, &[32, 556], BUILTIN, "Network Configuration Operators"),
+    (NT, &[32, 557], BUILTIN, "Incoming Forest Trust Builders"),
+    (NT, &[32, 558], BUILTIN, "Performance Monitor Users"),
+    (NT, &[32, 559], BUILTIN, "Performance Log Users"),
+    (
+        NT,
+        &[32, 560],
+        BUILTIN,
+        "Windows Authorization Access Group",
+    ),
+    (NT, &[32, 561], BUILTIN, "Terminal Server License Servers"),
+    (NT, &[32, 562], BUILTIN, "Distributed COM Users"),
+    (NT, &[32, 568], BUILTIN, "IIS_IUSRS"),
+    (NT, &[32, 569], BUILTIN, "Cryptographic Operators"),
+    (NT, &[32, 573], BUILTIN, "Event Log Readers"),
+    (NT, &[32, 574], BUILTIN, "Certificate Service DCOM Access"),
+    (NT, &[32, 575], BUILTIN, "RDS Remote Access Servers"),
+    (NT, &[32, 576], BUILTIN, "RDS Endpoint Servers"),
+    (NT, &[32, 577], BUILTIN, "RDS Management Servers"),
+    (NT, &[32, 578], BUILTIN, "Hyper-V Administrators"),
+    (
+        NT,
+        &[32, 579],
+        BUILTIN,
+        "Access Control Assistance Operators",
+    ),
+    (NT, &[32, 580], BUILTIN, "Remote Management Users"),
+    (NT, &[32, 581], BUILTIN, "System Managed Accounts Group"),
+    (NT, &[32, 582], BUILTIN, "Storage Replica Administrators"),
+    (NT, &[32, 583], BUILTIN, "Device Owners"),
+    (
+        APP_PACKAGE,
+        &[2, 1],
+        APPLICATION_PACKAGE_AUTHORITY,
+        "ALL APPLICATION PACKAGES",
+    ),
+    (
+        APP_PACKAGE,
+        &[2, 2],
+        APPLICATION_PACKAGE_AUTHORITY,
+        "ALL RESTRICTED APPLICATION PACKAGES",
+    ),
+    (
+        APP_PACKAGE,
+        &[3, 1],
+        APPLICATION_PACKAGE_AUTHORITY,
+        "Your Internet connection",
+    ),
+    (
+        APP_PACKAGE,
+        &[3, 2],
+        APPLICATION_PACKAGE_AUTHORITY,
+        "Your Internet connection, including incoming connections from the Internet",
+    ),
+    (
+        APP_PACKAGE,
+        &[3, 3],
+        APPLICATION_PACKAGE_AUTHORITY,
+        "Your home or work networks",
+    ),
+    (
+        APP_PACKAGE,
+        &[3, 4],
+        APPLICATION_PACKAGE_AUTHORITY,
+        "Your pictures library",
+    ),
+    (
+        APP_PACKAGE,
+        &[3, 5],
+        APPLICATION_PACKAGE_AUTHORITY,
+        "Your videos library",
+    ),
+    (
+        APP_PACKAGE,
+        &[3, 6],
+        APPLICATION_PACKAGE_AUTHORITY,
+        "Your music library",
+    ),
+    (
+        APP_PACKAGE,
+        &[3, 7],
+        APPLICATION_PACKAGE_AUTHORITY,
+        "Your documents library",
+    ),
+    (
+        APP_PACKAGE,
+        &[3, 8],
+        APPLICATION_PACKAGE_AUTHORITY,
+        "Your Windows credentials",
+    ),
+    (
+        APP_PACKAGE,
+        &[3, 9],
+        APPLICATION_PACKAGE_AUTHORITY,
+        "Software and hardware certificates or a smart card",
+    ),
+    (
+        APP_PACKAGE,
+        &[3, 10],
+        APPLICATION_PACKAGE_AUTHORITY,
+        "Removable storage",
+    ),
+    (
+        APP_PACKAGE,
+        &[3, 11],
+        APPLICATION_PACKAGE_AUTHORITY,
+        "Your Appointments",
+    ),
+    (
+        APP_PACKAGE,
+        &[3, 12],
+        APPLICATION_PACKAGE_AUTHORITY,
+        "Your Contacts",
+    ),
+    (
+        MANDATORY_LABEL,
+        &[0x0000],
+        MANDATORY_LABEL_DOMAIN,
+        "Untrusted Mandatory Level",
+    ),
+    (
+        MANDATORY_LABEL,
+        &[0x1000],
+        MANDATORY_LABEL_DOMAIN,
+        "Low Mandatory Level",
+    ),
+    (
+        MANDATORY_LABEL,
+        &[0x2000],
+        MANDATORY_LABEL_DOMAIN,
+        "Medium Mandatory Level",
+    ),
+    (
+        MANDATORY_LABEL,
+        &[0x2100],
+        MANDATORY_LABEL_DOMAIN,
+        "Medium Plus Mandatory Level",
+    ),
+    (
+        MANDATORY_LABEL,
+        &[0x3000],
+        MANDATORY_LABEL_DOMAIN,
+        "High Mandatory Level",
+    ),
+    (
+        MANDATORY_LABEL,
+        &[0x4000],
+        MANDATORY_LABEL_DOMAIN,
+        "System Mandatory Level",
+    ),
+    (
+        MANDATORY_LABEL,
+        &[0x5000],
+        MANDATORY_LABEL_DOMAIN,
+        "Protected Process Mandatory Level",
+    ),
+    (
+        AUTHENTICATION,
+        &[1],
+        "",
+        "Authentication authority asserted identity",
+    ),
+    (AUTHENTICATION, &[2], "", "Service asserted identity"),
+    (AUTHENTICATION, &[3], "", "Fresh public key identity"),
+    (AUTHENTICATION, &[4], "", "Key trust identity"),
+    (
+        AUTHENTICATION,
+        &[5],
+        "",
+        "Key property multi-factor authentication",
+    ),
+    (AUTHENTICATION, &[6], "", "Key property attestation"),
+];
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct AccountName {
+    domain: String,
+    name: String,
+}
+
+impl AccountName {
+    pub fn new<D, N>(domain: D, name: N) -> Self
+    where
+        D: Into<String>,
+        N: Into<String>,
+    {
+        AccountName {
+            domain: domain.into(),
+            name: name.into(),
+        }
+    }
+
+    pub fn parse(account_name: &str) -> Self {
+        match account_name.find('\\') {
+            Some(separator) => {
+                AccountName::new(&account_name[..separator], &account_name[separator + 1..])
+            }
+            None => AccountName::new("", account_name),
+        }
+    }
+
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn matches(&self, domain: &str, name: &str) -> bool {
+        (self.domain.is_empty() || self.domain.to_lowercase() == domain.to_lowercase())
+            && self.name.to_lowercase() == name.to_lowercase()
+    }
+
+    fn key(&self) -> (String, String) {
+        (self.domain.to_lowercase(), self.name.to_lowercase())
+    }
+}
+
+impl Display for AccountName {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        if self.domain.is_empty() {
+            write!(formatter, "{}", self.name)
+        } else {
+            write!(formatter, "{}\\{}", self.domain, self.name)
+        }
+    }
+}
+
+pub trait SidResolver {
+    fn lookup_sid(&self, sid: SecurityIdPtr) -> Option<AccountName>;
+
+    fn lookup_name(&self, account_name: &str) -> Option<SecurityId>;
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WellKnownSidResolver;
+
+impl SidResolver for WellKnownSidResolver {
+    fn lookup_sid(&self, sid: SecurityIdPtr) -> Option<AccountName> {
+        if let Some((high, low)) = sid.logon_session_id() {
+            return Some(AccountName::new(
+                NT_AUTHORITY,
+                format!("{}{}_{}", LOGON_SESSION_PREFIX, high, low),
+            ));
+        }
+
+        WELL_KNOWN_NAMES
+            .iter()
+            .find(|&&(authority, sub_authorities, _, _)| sid.matches(authority, sub_authorities))
+            .map(|&(_, _, domain, name)| AccountName::new(domain, name))
+    }
+
+    fn lookup_name(&self, account_name: &str) -> Option<SecurityId> {
+        let account_name = AccountName::parse(account_name);
+        let well_known_sid = WELL_KNOWN_NAMES
+            .iter()
+            .find(|&&(_, _, domain, name)| account_name.matches(domain, name))
+            .and_then(|&(authority, sub_authorities, _, _)| {
+                SecurityId::new(authority, sub_authorities).ok()
+            });
+
+        if well_known_sid.is_some() {
+            return well_known_sid;
+        }
+
+        match account_name.domain().to_uppercase().as_str() {
+            NT_SERVICE => Some(SecurityId::service(account_name.name())),
+            IIS_APPPOOL => Some(SecurityId::iis_app_pool(account_name.name())),
+            NT_TASK => Some(SecurityId::scheduled_task(account_name.name())),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum LoadSidDatabaseError {
+    #[fail(display = "Failed to read security ID database")]
+    Io(#[cause] io::Error),
+
+    #[fail(display = "Invalid security ID database entry on line {}", _0)]
+    InvalidEntry(usize),
+
+    #[fail(
+        display = "Duplicate account name in security ID database on line {}",
+        _0
+    )]
+    DuplicateName(usize),
+}
+
+#[derive(Debug, Fail)]
+#[fail(display = "Account name {} is already assigned to {}", name, sid)]
+pub struct DuplicateAccountNameError {
+    name: AccountName,
+    sid: SecurityId,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SidDatabase {
+    names: HashMap<SecurityId, AccountName>,
+    sids: HashMap<(String, String), SecurityId>,
+}
+
+impl SidDatabase {
+    pub fn new() -> Self {
+        SidDatabase::default()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LoadSidDatabaseError> {
+        let file = File::open(path).map_err(LoadSidDatabaseError::Io)?;
+
+        SidDatabase::from_reader(BufReader::new(file))
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, LoadSidDatabaseError> {
+        let mut database = SidDatabase::new();
+
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(LoadSidDatabaseError::Io)?;
+            let entry = line.trim();
+
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+
+            let separator = entry
+                .find('=')
+                .ok_or(LoadSidDatabaseError::InvalidEntry(index + 1))?;
+            let sid = entry[..separator]
+                .trim()
+                .parse()
+                .map_err(|_| LoadSidDatabaseError::InvalidEntry(index + 1))?;
+            let account_name = AccountName::parse(entry[separator + 1..].trim());
+
+            database
+                .insert(sid, account_name)
+                .map_err(|_| LoadSidDatabaseError::DuplicateName(index + 1))?;
+        }
+
+        Ok(database)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        self.write_to(&mut writer)?;
+
+        writer.flush()
+    }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut entries: Vec<_> = self.names.iter().collect();
+
+        entries.sort_by_key(|&(sid, _)| sid);
+
+        for (sid, account_name) in entries {
+            writeln!(writer, "{}={}", sid, account_name)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn insert(
+        &mut self,
+        sid: SecurityId,
+        account_name: AccountName,
+    ) -> Result<(), DuplicateAccountNameError> {
+        let key = account_name.key();
+
+        if let Some(existing_sid) = self.sids.get(&key) {
+            if *existing_sid != sid {
+                return Err(DuplicateAccountNameError {
+                    name: account_name,
+                    sid: existing_sid.clone(),
+                });
+            }
+        }
+
+        if let Some(previous_name) = self.names.insert(sid.clone(), account_name) {
+            self.sids.remove(&previous_name.key());
+        }
+
+        self.sids.insert(key, sid);
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+impl SidResolver for SidDatabase {
+    fn lookup_sid(&self, sid: SecurityIdPtr) -> Option<AccountName> {
+        self.names
+            .get(sid.as_bytes())
+            .cloned()
+            .or_else(|| WellKnownSidResolver.lookup_sid(sid))
+    }
+
+    fn lookup_name(&self, account_name: &str) -> Option<SecurityId> {
+        let parsed_name = AccountName::parse(account_name);
+        let sid = if parsed_name.domain.is_empty() {
+            let mut candidates = self
+                .names
+                .iter()
+                .filter(|&(_, name)| parsed_name.matches(&name.domain, &name.name));
+
+            match (candidates.next(), candidates.next()) {
+                (Some((sid, _)), None) => Some(sid.clone()),
+                _ => None,
+            }
+        } else {
+            self.sids.get(&parsed_name.key()).cloned()
+        };
+
+        sid.or_else(|| WellKnownSidResolver.lookup_name(account_name))
+    }
+}
+
+pub struct ResolvedSecurityId<'a, 'r, R: SidResolver + ?Sized + 'r> {
+    sid: SecurityIdPtr<'a>,
+    resolver: &'r R,
+}
+
+impl<'a, 'r, R: SidResolver + ?Sized> Display for ResolvedSecurityId<'a, 'r, R> {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self.resolver.lookup_sid(self.sid) {
+            Some(account_name) => Display::fmt(&account_name, formatter),
+            None => Display::fmt(&self.sid, formatter),
+        }
+    }
+}
+
+impl<'a> SecurityIdPtr<'a> {
+    pub fn account_name<R: SidResolver + ?Sized>(&self, resolver: &R) -> Option<AccountName> {
+        resolver.lookup_sid(*self)
+    }
+
+    pub fn display_with<'r, R: SidResolver + ?Sized>(
+        &self,
+        resolver: &'r R,
+    ) -> ResolvedSecurityId<'a, 'r, R> {
+        ResolvedSecurityId {
+            sid: *self,
+            resolver,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATABASE: &str = "# Exported accounts\n\
+                            \n\
+                            S-1-5-21-1-2-3-1002 = FABRIKAM\\alice\n\
+                            S-1-5-21-1-2-3-1001=CONTOSO\\alice\n\
+                            S-1-5-21-1-2-3-1003=CONTOSO\\bob\n";
+
+    fn sid(sid_string: &str) -> SecurityId {
+        sid_string.parse().unwrap()
+    }
+
+    fn load(database: &str) -> Result<SidDatabase, LoadSidDatabaseError> {
+        SidDatabase::from_reader(database.as_bytes())
+    }
+
+    #[test]
+    fn loads_and_saves_databases() {
+        let database = load(DATABASE).unwrap();
+        let mut saved = Vec::new();
+
+        database.write_to(&mut saved).unwrap();
+
+        assert_eq!(database.len(), 3);
+        assert_eq!(
+            String::from_utf8(saved.clone()).unwrap(),
+            "S-1-5-21-1-2-3-1001=CONTOSO\\alice\n\
+             S-1-5-21-1-2-3-1002=FABRIKAM\\alice\n\
+             S-1-5-21-1-2-3-1003=CONTOSO\\bob\n"
+        );
+        assert_eq!(
+            SidDatabase::from_reader(&saved[..]).unwrap().len(),
+            database.len()
+        );
+    }
+
+    #[test]
+    fn reports_the_line_of_invalid_entries() {
+        match load("S-1-5-18=SYSTEM\nCONTOSO\\alice\n") {
+            Err(LoadSidDatabaseError::InvalidEntry(2)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match load("\nS-1-5-x=CONTOSO\\alice\n") {
+            Err(LoadSidDatabaseError::InvalidEntry(2)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match load("S-1-5-21-1=CONTOSO\\alice\nS-1-5-21-2=contoso\\ALICE\n") {
+            Err(LoadSidDatabaseError::DuplicateName(2)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn resolves_only_unambiguous_names() {
+        let database = load(DATABASE).unwrap();
+
+        assert_eq!(
+            database.lookup_name("contoso\\ALICE"),
+            Some(sid("S-1-5-21-1-2-3-1001"))
+        );
+        assert_eq!(
+            database.lookup_name("FABRIKAM\\alice"),
+            Some(sid("S-1-5-21-1-2-3-1002"))
+        );
+        assert_eq!(database.lookup_name("alice"), None);
+        assert_eq!(
+            database.lookup_name("bob"),
+            Some(sid("S-1-5-21-1-2-3-1003"))
+        );
+        assert_eq!(
+            database.lookup_name("NT AUTHORITY\\SYSTEM"),
+            Some(sid("S-1-5-18"))
+        );
+        assert_eq!(
+            database
+                .lookup_sid(sid("S-1-5-21-1-2-3-1003").as_sid_ptr())
+                .map(|name| name.to_string()),
+            Some("CONTOSO\\bob".to_owned())
+        );
+    }
+
+    #[test]
+    fn renaming_a_security_id_releases_its_old_name() {
+        let mut database = load(DATABASE).unwrap();
+
+        database
+            .insert(
+                sid("S-1-5-21-1-2-3-1003"),
+                AccountName::new("CONTOSO", "robert"),
+            )
+            .unwrap();
+
+        assert_eq!(database.lookup_name("CONTOSO\\bob"), None);
+        assert!(database
+            .insert(
+                sid("S-1-5-21-1-2-3-1004"),
+                AccountName::new("CONTOSO", "bob")
+            )
+            .is_ok());
+        assert!(database
+            .insert(
+                sid("S-1-5-21-1-2-3-1005"),
+                AccountName::new("CONTOSO", "Robert")
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,244 @@
+use std::cmp::Ordering;
+
+use super::{IdentifierAuthority, SecurityId, SecurityIdPtr};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[repr(u32)]
+pub enum ProtectionType {
+    None = 0x0000,
+    Lite = 0x0200,
+    Full = 0x0400,
+}
+
+impl ProtectionType {
+    pub fn from_rid(rid: u32) -> Option<Self> {
+        match rid {
+            0x0000 => Some(ProtectionType::None),
+            0x0200 => Some(ProtectionType::Lite),
+            0x0400 => Some(ProtectionType::Full),
+            _ => None,
+        }
+    }
+
+    pub fn rid(self) -> u32 {
+        self as u32
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[repr(u32)]
+pub enum ProtectionLevel {
+    None = 0x0000,
+    Authenticode = 0x0400,
+    Antimalware = 0x0600,
+    App = 0x0800,
+    Windows = 0x1000,
+    WinTcb = 0x2000,
+}
+
+impl ProtectionLevel {
+    pub fn from_rid(rid: u32) -> Option<Self> {
+        match rid {
+            0x0000 => Some(ProtectionLevel::None),
+            0x0400 => Some(ProtectionLevel::Authenticode),
+            0x0600 => Some(ProtectionLevel::Antimalware),
+            0x0800 => Some(ProtectionLevel::App),
+            0x1000 => Some(ProtectionLevel::Windows),
+            0x2000 => Some(ProtectionLevel::WinTcb),
+            _ => None,
+        }
+    }
+
+    pub fn rid(self) -> u32 {
+        self as u32
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct TrustLabel {
+    protection_type: ProtectionType,
+    protection_level: ProtectionLevel,
+}
+
+impl TrustLabel {
+    pub fn new(protection_type: ProtectionType, protection_level: ProtectionLevel) -> Self {
+        TrustLabel {
+            protection_type,
+            protection_level,
+        }
+    }
+
+    pub fn protection_type(&self) -> ProtectionType {
+        self.protection_type
+    }
+
+    pub fn protection_level(&self) -> ProtectionLevel {
+        self.protection_level
+    }
+
+    pub fn dominates(&self, other: &TrustLabel) -> bool {
+        self.protection_type >= other.protection_type
+            && self.protection_level >= other.protection_level
+    }
+}
+
+impl PartialOrd for TrustLabel {
+    fn partial_cmp(&self, other: &TrustLabel) -> Option<Ordering> {
+        match (self.dominates(other), other.dominates(self)) {
+            (true, true) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Greater),
+            (false, true) => Some(Ordering::Less),
+            (false, false) => None,
+        }
+    }
+}
+
+impl From<TrustLabel> for SecurityId {
+    fn from(trust_label: TrustLabel) -> Self {
+        SecurityId::trust_label(trust_label)
+    }
+}
+
+impl SecurityId {
+    pub fn trust_label(trust_label: TrustLabel) -> Self {
+        SecurityId::from_parts(
+            IdentifierAuthority::PROCESS_TRUST,
+            &[
+                trust_label.protection_type.rid(),
+                trust_label.protection_level.rid(),
+            ],
+        )
+    }
+}
+
+impl<'a> SecurityIdPtr<'a> {
+    pub fn is_trust_label(&self) -> bool {
+        self.identifier_authority() == IdentifierAuthority::PROCESS_TRUST
+            && self.sub_authority_count() == 2
+    }
+
+    pub fn trust_label(&self) -> Option<TrustLabel> {
+        if !self.is_trust_label() {
+            return None;
+        }
+
+        let protection_type = ProtectionType::from_rid(self.sub_authority(0)?)?;
+        let protection_level = ProtectionLevel::from_rid(self.sub_authority(1)?)?;
+
+        Some(TrustLabel::new(protection_type, protection_level))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(protection_type: ProtectionType, protection_level: ProtectionLevel) -> TrustLabel {
+        TrustLabel::new(protection_type, protection_level)
+    }
+
+    #[test]
+    fn converts_trust_labels_to_security_ids() {
+        let win_tcb = label(ProtectionType::Full, ProtectionLevel::WinTcb);
+        let sid = SecurityId::from(win_tcb);
+
+        assert_eq!(sid.to_string(), "S-1-19-1024-8192");
+        assert!(sid.is_trust_label());
+        assert_eq!(sid.as_sid_ptr().trust_label(), Some(win_tcb));
+
+        let lite: SecurityId = "S-1-19-512-1536".parse().unwrap();
+
+        assert_eq!(
+            lite.as_sid_ptr().trust_label(),
+            Some(label(ProtectionType::Lite, ProtectionLevel::Antimalware))
+        );
+
+        for sid in &["S-1-19-768-1024", "S-1-19-512-1000"] {
+            let sid: SecurityId = sid.parse().unwrap();
+
+            assert!(sid.is_trust_label());
+            assert_eq!(sid.as_sid_ptr().trust_label(), None);
+        }
+
+        for sid in &["S-1-19-512", "S-1-19-512-1024-1", "S-1-16-512-1024"] {
+            let sid: SecurityId = sid.parse().unwrap();
+
+            assert!(!sid.is_trust_label());
+            assert_eq!(sid.as_sid_ptr().trust_label(), None);
+        }
+    }
+
+    #[test]
+    fn maps_rids_to_protection_types_and_levels() {
+        for &protection_type in &[
+            ProtectionType::None,
+            ProtectionType::Lite,
+            ProtectionType::Full,
+        ] {
+            assert_eq!(
+                ProtectionType::from_rid(protection_type.rid()),
+                Some(protection_type)
+            );
+        }
+
+        for &protection_level in &[
+            ProtectionLevel::None,
+            ProtectionLevel::Authenticode,
+            ProtectionLevel::Antimalware,
+            ProtectionLevel::App,
+            ProtectionLevel::Windows,
+            ProtectionLevel::WinTcb,
+        ] {
+            assert_eq!(
+                ProtectionLevel::from_rid(protection_level.rid()),
+                Some(protection_level)
+            );
+        }
+
+        assert_eq!(ProtectionType::from_rid(0x0300), None);
+        assert_eq!(ProtectionLevel::from_rid(0x0500), None);
+        assert!(ProtectionLevel::Authenticode < ProtectionLevel::Antimalware);
+        assert!(ProtectionLevel::Windows < ProtectionLevel::WinTcb);
+        assert!(ProtectionType::Lite < ProtectionType::Full);
+    }
+
+    #[test]
+    fn dominates_only_when_both_parts_are_at_least_as_high() {
+        let full_win_tcb = label(ProtectionType::Full, ProtectionLevel::WinTcb);
+        let full_authenticode = label(ProtectionType::Full, ProtectionLevel::Authenticode);
+        let lite_win_tcb = label(ProtectionType::Lite, ProtectionLevel::WinTcb);
+        let lite_antimalware = label(ProtectionType::Lite, ProtectionLevel::Antimalware);
+
+        assert!(full_win_tcb.dominates(&full_win_tcb));
+        assert!(full_win_tcb.dominates(&lite_antimalware));
+        assert!(!lite_antimalware.dominates(&full_win_tcb));
+        assert!(lite_win_tcb.dominates(&lite_antimalware));
+        assert!(full_win_tcb.dominates(&full_authenticode));
+        assert!(!full_authenticode.dominates(&lite_antimalware));
+
+        assert_eq!(
+            full_win_tcb.partial_cmp(&full_win_tcb),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            full_win_tcb.partial_cmp(&lite_win_tcb),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            lite_antimalware.partial_cmp(&lite_win_tcb),
+            Some(Ordering::Less)
+        );
+        assert!(lite_antimalware < full_win_tcb);
+    }
+
+    #[test]
+    fn leaves_mixed_trust_labels_unordered() {
+        let full_authenticode = label(ProtectionType::Full, ProtectionLevel::Authenticode);
+        let lite_win_tcb = label(ProtectionType::Lite, ProtectionLevel::WinTcb);
+
+        assert!(!full_authenticode.dominates(&lite_win_tcb));
+        assert!(!lite_win_tcb.dominates(&full_authenticode));
+        assert_eq!(full_authenticode.partial_cmp(&lite_win_tcb), None);
+        assert_eq!(lite_win_tcb.partial_cmp(&full_authenticode), None);
+    }
+}
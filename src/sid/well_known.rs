@@ -0,0 +1,329 @@
+use super::{IdentifierAuthority, InvalidSecurityIdError, SecurityId, SecurityIdPtr};
+
+const SECURITY_LOGON_IDS_RID: u32 = 5;
+const SECURITY_LOGON_IDS_RID_COUNT: usize = 3;
+const SECURITY_NT_NON_UNIQUE: u32 = 21;
+const SECURITY_NT_NON_UNIQUE_SUB_AUTH_COUNT: usize = 3;
+
+enum Definition {
+    Fixed(IdentifierAuthority, &'static [u32]),
+    DomainRelative(u32),
+    LogonSession,
+}
+
+macro_rules! well_known_sids {
+    ($( $variant:ident = $value:expr => $definition:expr, )*) => {
+        #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+        #[repr(u32)]
+        pub enum WellKnownSid {
+            $( $variant = $value, )*
+        }
+
+        const WELL_KNOWN_SIDS: &[(WellKnownSid, Definition)] = &[
+            $( (WellKnownSid::$variant, $definition), )*
+        ];
+    };
+}
+
+use self::Definition::{DomainRelative, Fixed, LogonSession};
+
+const NULL: IdentifierAuthority = IdentifierAuthority::NULL;
+const WORLD: IdentifierAuthority = IdentifierAuthority::WORLD;
+const LOCAL: IdentifierAuthority = IdentifierAuthority::LOCAL;
+const CREATOR: IdentifierAuthority = IdentifierAuthority::CREATOR;
+const NT: IdentifierAuthority = IdentifierAuthority::NT;
+const APP_PACKAGE: IdentifierAuthority = IdentifierAuthority::APP_PACKAGE;
+const MANDATORY_LABEL: IdentifierAuthority = IdentifierAuthority::MANDATORY_LABEL;
+const AUTHENTICATION: IdentifierAuthority = IdentifierAuthority::AUTHENTICATION;
+
+well_known_sids! {
+    Null = 0 => Fixed(NULL, &[0]),
+    World = 1 => Fixed(WORLD, &[0]),
+    Local = 2 => Fixed(LOCAL, &[0]),
+    CreatorOwner = 3 => Fixed(CREATOR, &[0]),
+    CreatorGroup = 4 => Fixed(CREATOR, &[1]),
+    CreatorOwnerServer = 5 => Fixed(CREATOR, &[2]),
+    CreatorGroupServer = 6 => Fixed(CREATOR, &[3]),
+    NtAuthority = 7 => Fixed(NT, &[]),
+    Dialup = 8 => Fixed(NT, &[1]),
+    Network = 9 => Fixed(NT, &[2]),
+    Batch = 10 => Fixed(NT, &[3]),
+    Interactive = 11 => Fixed(NT, &[4]),
+    Service = 12 => Fixed(NT, &[6]),
+    Anonymous = 13 => Fixed(NT, &[7]),
+    Proxy = 14 => Fixed(NT, &[8]),
+    EnterpriseControllers = 15 => Fixed(NT, &[9]),
+    PrincipalSelf = 16 => Fixed(NT, &[10]),
+    AuthenticatedUser = 17 => Fixed(NT, &[11]),
+    RestrictedCode = 18 => Fixed(NT, &[12]),
+    TerminalServer = 19 => Fixed(NT, &[13]),
+    RemoteLogonId = 20 => Fixed(NT, &[14]),
+    LogonIds = 21 => LogonSession,
+    LocalSystem = 22 => Fixed(NT, &[18]),
+    LocalService = 23 => Fixed(NT, &[19]),
+    NetworkService = 24 => Fixed(NT, &[20]),
+    BuiltinDomain = 25 => Fixed(NT, &[32]),
+    BuiltinAdministrators = 26 => Fixed(NT, &[32, 544]),
+    BuiltinUsers = 27 => Fixed(NT, &[32, 545]),
+    BuiltinGuests = 28 => Fixed(NT, &[32, 546]),
+    BuiltinPowerUsers = 29 => Fixed(NT, &[32, 547]),
+    BuiltinAccountOperators = 30 => Fixed(NT, &[32, 548]),
+    BuiltinSystemOperators = 31 => Fixed(NT, &[32, 549]),
+    BuiltinPrintOperators = 32 => Fixed(NT, &[32, 550]),
+    BuiltinBackupOperators = 33 => Fixed(NT, &[32, 551]),
+    BuiltinReplicator = 34 => Fixed(NT, &[32, 552]),
+    BuiltinPreWindows2000CompatibleAccess = 35 => Fixed(NT, &[32, 554]),
+    BuiltinRemoteDesktopUsers = 36 => Fixed(NT, &[32, 555]),
+    BuiltinNetworkConfigurationOperators = 37 => Fixed(NT, &[32, 556]),
+    AccountAdministrator = 38 => DomainRelative(500),
+    AccountGuest = 39 => DomainRelative(501),
+    AccountKrbtgt = 40 => DomainRelative(502),
+    AccountDomainAdmins = 41 => DomainRelative(512),
+    AccountDomainUsers = 42 => DomainRelative(513),
+    AccountDomainGuests = 43 => DomainRelative(514),
+    AccountComputers = 44 => DomainRelative(515),
+    AccountControllers = 45 => DomainRelative(516),
+    AccountCertAdmins = 46 => DomainRelative(517),
+    AccountSchemaAdmins = 47 => DomainRelative(518),
+    AccountEnterpriseAdmins = 48 => DomainRelative(519),
+    AccountPolicyAdmins = 49 => DomainRelative(520),
+    AccountRasAndIasServers = 50 => DomainRelative(553),
+    NtlmAuthentication = 51 => Fixed(NT, &[64, 10]),
+    DigestAuthentication = 52 => Fixed(NT, &[64, 21]),
+    SChannelAuthentication = 53 => Fixed(NT, &[64, 14]),
+    ThisOrganization = 54 => Fixed(NT, &[15]),
+    OtherOrganization = 55 => Fixed(NT, &[1000]),
+    BuiltinIncomingForestTrustBuilders = 56 => Fixed(NT, &[32, 557]),
+    BuiltinPerfMonitoringUsers = 57 => Fixed(NT, &[32, 558]),
+    BuiltinPerfLoggingUsers = 58 => Fixed(NT, &[32, 559]),
+    BuiltinAuthorizationAccess = 59 => Fixed(NT, &[32, 560]),
+    BuiltinTerminalServerLicenseServers = 60 => Fixed(NT, &[32, 561]),
+    BuiltinDcomUsers = 61 => Fixed(NT, &[32, 562]),
+    BuiltinIUsers = 62 => Fixed(NT, &[32, 568]),
+    IUser = 63 => Fixed(NT, &[17]),
+    BuiltinCryptoOperators = 64 => Fixed(NT, &[32, 569]),
+    UntrustedLabel = 65 => Fixed(MANDATORY_LABEL, &[0x0000]),
+    LowLabel = 66 => Fixed(MANDATORY_LABEL, &[0x1000]),
+    MediumLabel = 67 => Fixed(MANDATORY_LABEL, &[0x2000]),
+    HighLabel = 68 => Fixed(MANDATORY_LABEL, &[0x3000]),
+    SystemLabel = 69 => Fixed(MANDATORY_LABEL, &[0x4000]),
+    WriteRestrictedCode = 70 => Fixed(NT, &[33]),
+    CreatorOwnerRights = 71 => Fixed(CREATOR, &[4]),
+    CacheablePrincipalsGroup = 72 => DomainRelative(571),
+    NonCacheablePrincipalsGroup = 73 => DomainRelative(572),
+    EnterpriseReadonlyControllers = 74 => DomainRelative(498),
+    AccountReadonlyControllers = 75 => DomainRelative(521),
+    BuiltinEventLogReadersGroup = 76 => Fixed(NT, &[32, 573]),
+    NewEnterpriseReadonlyControllers = 77 => Fixed(NT, &[22]),
+    BuiltinCertSvcDcomAccessGroup = 78 => Fixed(NT, &[32, 574]),
+    MediumPlusLabel = 79 => Fixed(MANDATORY_LABEL, &[0x2100]),
+    LocalLogon = 80 => Fixed(LOCAL, &[1]),
+    ConsoleLogon = 81 => Fixed(LOCAL, &[1]),
+    ThisOrganizationCertificate = 82 => Fixed(NT, &[65, 1]),
+    ApplicationPackageAuthority = 83 => Fixed(APP_PACKAGE, &[]),
+    BuiltinAnyPackage = 84 => Fixed(APP_PACKAGE, &[2, 1]),
+    CapabilityInternetClient = 85 => Fixed(APP_PACKAGE, &[3, 1]),
+    CapabilityInternetClientServer = 86 => Fixed(APP_PACKAGE, &[3, 2]),
+    CapabilityPrivateNetworkClientServer = 87 => Fixed(APP_PACKAGE, &[3, 3]),
+    CapabilityPicturesLibrary = 88 => Fixed(APP_PACKAGE, &[3, 4]),
+    CapabilityVideosLibrary = 89 => Fixed(APP_PACKAGE, &[3, 5]),
+    CapabilityMusicLibrary = 90 => Fixed(APP_PACKAGE, &[3, 6]),
+    CapabilityDocumentsLibrary = 91 => Fixed(APP_PACKAGE, &[3, 7]),
+    CapabilitySharedUserCertificates = 92 => Fixed(APP_PACKAGE, &[3, 9]),
+    CapabilityEnterpriseAuthentication = 93 => Fixed(APP_PACKAGE, &[3, 8]),
+    CapabilityRemovableStorage = 94 => Fixed(APP_PACKAGE, &[3, 10]),
+    BuiltinRdsRemoteAccessServers = 95 => Fixed(NT, &[32, 575]),
+    BuiltinRdsEndpointServers = 96 => Fixed(NT, &[32, 576]),
+    BuiltinRdsManagementServers = 97 => Fixed(NT, &[32, 577]),
+    UserModeDrivers = 98 => Fixed(NT, &[84, 0, 0, 0, 0, 0]),
+    BuiltinHyperVAdmins = 99 => Fixed(NT, &[32, 578]),
+    AccountCloneableControllers = 100 => DomainRelative(522),
+    BuiltinAccessControlAssistanceOperators = 101 => Fixed(NT, &[32, 579]),
+    BuiltinRemoteManagementUsers = 102 => Fixed(NT, &[32, 580]),
+    AuthenticationAuthorityAsserted = 103 => Fixed(AUTHENTICATION, &[1]),
+    AuthenticationServiceAsserted = 104 => Fixed(AUTHENTICATION, &[2]),
+    LocalAccount = 105 => Fixed(NT, &[113]),
+    LocalAccountAndAdministrator = 106 => Fixed(NT, &[114]),
+    AccountProtectedUsers = 107 => DomainRelative(525),
+    CapabilityAppointments = 108 => Fixed(APP_PACKAGE, &[3, 11]),
+    CapabilityContacts = 109 => Fixed(APP_PACKAGE, &[3, 12]),
+    AccountDefaultSystemManaged = 110 => DomainRelative(503),
+    BuiltinDefaultSystemManagedGroup = 111 => Fixed(NT, &[32, 581]),
+    BuiltinStorageReplicaAdmins = 112 => Fixed(NT, &[32, 582]),
+    AccountKeyAdmins = 113 => DomainRelative(526),
+    AccountEnterpriseKeyAdmins = 114 => DomainRelative(527),
+    AuthenticationKeyTrust = 115 => Fixed(AUTHENTICATION, &[4]),
+    AuthenticationKeyPropertyMfa = 116 => Fixed(AUTHENTICATION, &[5]),
+    AuthenticationKeyPropertyAttestation = 117 => Fixed(AUTHENTICATION, &[6]),
+    AuthenticationFreshKeyAuth = 118 => Fixed(AUTHENTICATION, &[3]),
+    BuiltinDeviceOwners = 119 => Fixed(NT, &[32, 583]),
+}
+
+impl WellKnownSid {
+    pub fn from_raw(value: u32) -> Option<Self> {
+        WELL_KNOWN_SIDS
+            .iter()
+            .map(|&(well_known_sid, _)| well_known_sid)
+            .find(|&well_known_sid| well_known_sid as u32 == value)
+    }
+
+    pub fn is_domain_relative(self) -> bool {
+        match *self.definition() {
+            DomainRelative(_) => true,
+            Fixed(..) | LogonSession => false,
+        }
+    }
+
+    fn definition(self) -> &'static Definition {
+        WELL_KNOWN_SIDS
+            .iter()
+            .find(|&&(well_known_sid, _)| well_known_sid == self)
+            .map(|(_, definition)| definition)
+            .expect("Missing well-known security ID definition")
+    }
+}
+
+impl SecurityId {
+    pub fn well_known(well_known_sid: WellKnownSid) -> Result<Self, InvalidSecurityIdError> {
+        match *well_known_sid.definition() {
+            Fixed(authority, sub_authorities) => SecurityId::new(authority, sub_authorities),
+            LogonSession => Err(InvalidSecurityIdError::LogonSession),
+            DomainRelative(_) => Err(InvalidSecurityIdError::DomainRelative(well_known_sid)),
+        }
+    }
+
+    pub fn well_known_in_domain<'a, D>(
+        well_known_sid: WellKnownSid,
+        domain: D,
+    ) -> Result<Self, InvalidSecurityIdError>
+    where
+        D: Into<SecurityIdPtr<'a>>,
+    {
+        match *well_known_sid.definition() {
+            DomainRelative(rid) => {
+                let domain = domain.into();
+                let mut sub_authorities = domain.sub_authorities();
+
+                if domain.identifier_authority() != NT
+                    || sub_authorities.len() != SECURITY_NT_NON_UNIQUE_SUB_AUTH_COUNT + 1
+                    || sub_authorities.next() != Some(SECURITY_NT_NON_UNIQUE)
+                {
+                    return Err(InvalidSecurityIdError::NotDomain);
+                }
+
+                domain.with_rid(rid)
+            }
+            Fixed(..) | LogonSession => SecurityId::well_known(well_known_sid),
+        }
+    }
+}
+
+impl<'a> SecurityIdPtr<'a> {
+    pub fn well_known(&self) -> Option<WellKnownSid> {
+        WELL_KNOWN_SIDS
+            .iter()
+            .map(|&(well_known_sid, _)| well_known_sid)
+            .find(|&well_known_sid| self.is_well_known(well_known_sid))
+    }
+
+    pub fn is_well_known(&self, well_known_sid: WellKnownSid) -> bool {
+        match *well_known_sid.definition() {
+            Fixed(authority, sub_authorities) => self.matches(authority, sub_authorities),
+            LogonSession => {
+                self.identifier_authority() == NT
+                    && self.sub_authorities().len() == SECURITY_LOGON_IDS_RID_COUNT
+                    && self.sub_authorities().next() == Some(SECURITY_LOGON_IDS_RID)
+            }
+            DomainRelative(rid) => {
+                let mut sub_authorities = self.sub_authorities();
+
+                self.identifier_authority() == NT
+                    && sub_authorities.len() == SECURITY_NT_NON_UNIQUE_SUB_AUTH_COUNT + 2
+                    && sub_authorities.next() == Some(SECURITY_NT_NON_UNIQUE)
+                    && sub_authorities.last() == Some(rid)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logon_session_requires_an_id() {
+        match SecurityId::well_known(WellKnownSid::LogonIds) {
+            Err(InvalidSecurityIdError::LogonSession) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let sid = SecurityId::logon_session(0, 0x3e7);
+
+        assert_eq!(sid.to_string(), "S-1-5-5-0-999");
+        assert!(sid.as_sid_ptr().is_well_known(WellKnownSid::LogonIds));
+        assert_eq!(sid.logon_session_id(), Some((0, 0x3e7)));
+    }
+
+    #[test]
+    fn looks_up_well_known_security_ids() {
+        let administrators = SecurityId::well_known(WellKnownSid::BuiltinAdministrators).unwrap();
+        let unknown: SecurityId = "S-1-5-32-1000".parse().unwrap();
+
+        assert_eq!(administrators.to_string(), "S-1-5-32-544");
+        assert_eq!(
+            administrators.as_sid_ptr().well_known(),
+            Some(WellKnownSid::BuiltinAdministrators)
+        );
+        assert_eq!(unknown.as_sid_ptr().well_known(), None);
+        assert_eq!(
+            WellKnownSid::from_raw(WellKnownSid::LocalSystem as u32),
+            Some(WellKnownSid::LocalSystem)
+        );
+    }
+
+    #[test]
+    fn builds_domain_relative_security_ids() {
+        let domain: SecurityId = "S-1-5-21-1-2-3".parse().unwrap();
+
+        assert!(WellKnownSid::AccountAdministrator.is_domain_relative());
+        assert!(!WellKnownSid::BuiltinAdministrators.is_domain_relative());
+        assert!(!WellKnownSid::LogonIds.is_domain_relative());
+
+        match SecurityId::well_known(WellKnownSid::AccountAdministrator) {
+            Err(InvalidSecurityIdError::DomainRelative(WellKnownSid::AccountAdministrator)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let administrator =
+            SecurityId::well_known_in_domain(WellKnownSid::AccountAdministrator, &domain).unwrap();
+
+        assert_eq!(administrator.to_string(), "S-1-5-21-1-2-3-500");
+        assert_eq!(
+            administrator.as_sid_ptr().well_known(),
+            Some(WellKnownSid::AccountAdministrator)
+        );
+
+        let local_system =
+            SecurityId::well_known_in_domain(WellKnownSid::LocalSystem, &domain).unwrap();
+
+        assert_eq!(local_system.to_string(), "S-1-5-18");
+    }
+
+    #[test]
+    fn rejects_domains_that_are_not_account_domains() {
+        for domain in &[
+            "S-1-5-32",
+            "S-1-5-21-1-2",
+            "S-1-5-21-1-2-3-4",
+            "S-1-5-22-1-2-3",
+            "S-1-1-21-1-2-3",
+        ] {
+            let domain: SecurityId = domain.parse().unwrap();
+
+            match SecurityId::well_known_in_domain(WellKnownSid::AccountAdministrator, &domain) {
+                Err(InvalidSecurityIdError::NotDomain) => {}
+                other => panic!("unexpected result for {}: {:?}", domain, other),
+            }
+        }
+    }
+}
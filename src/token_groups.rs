@@ -0,0 +1,347 @@
+use std::iter::FromIterator;
+use std::slice;
+
+use super::{AccessControlEntryPtr, IntegrityLevel, SecurityId, SecurityIdPtr};
+
+bitflags! {
+    pub struct GroupAttributes: u32 {
+        const MANDATORY = 0x0000_0001;
+        const ENABLED_BY_DEFAULT = 0x0000_0002;
+        const ENABLED = 0x0000_0004;
+        const OWNER = 0x0000_0008;
+        const USE_FOR_DENY_ONLY = 0x0000_0010;
+        const INTEGRITY = 0x0000_0020;
+        const INTEGRITY_ENABLED = 0x0000_0040;
+        const RESOURCE = 0x2000_0000;
+        const LOGON_ID = 0xC000_0000;
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct TokenGroups {
+    groups: Vec<(SecurityId, GroupAttributes)>,
+    history: Vec<SecurityId>,
+}
+
+impl TokenGroups {
+    pub fn new() -> Self {
+        TokenGroups::default()
+    }
+
+    pub fn insert(&mut self, sid: SecurityId, attributes: GroupAttributes) {
+        match self.position(sid.as_sid_ptr()) {
+            Some(index) => self.groups[index].1 = attributes,
+            None => self.groups.push((sid, attributes)),
+        }
+    }
+
+    /// Adds a security ID from the account's SID history.
+    ///
+    /// Windows places SID history entries in the token as enabled mandatory groups, so entries
+    /// that name the old security ID apply to the token. A security ID that is already a group
+    /// keeps its attributes.
+    pub fn insert_history(&mut self, sid: SecurityId) {
+        if !self.contains(&sid) {
+            let attributes = GroupAttributes::MANDATORY
+                | GroupAttributes::ENABLED_BY_DEFAULT
+                | GroupAttributes::ENABLED;
+
+            self.insert(sid.clone(), attributes);
+        }
+
+        if !self.is_history(&sid) {
+            self.history.push(sid);
+        }
+    }
+
+    pub fn remove<'a, S: Into<SecurityIdPtr<'a>>>(&mut self, sid: S) -> Option<GroupAttributes> {
+        let sid = sid.into();
+        let index = self.position(sid)?;
+
+        self.history.retain(|entry| *entry != sid);
+
+        Some(self.groups.remove(index).1)
+    }
+
+    pub fn is_history<'a, S: Into<SecurityIdPtr<'a>>>(&self, sid: S) -> bool {
+        let sid = sid.into();
+
+        self.history.iter().any(|entry| *entry == sid)
+    }
+
+    pub fn history<'a>(&'a self) -> SidHistory<'a> {
+        SidHistory {
+            sids: self.history.iter(),
+        }
+    }
+
+    pub fn attributes<'a, S: Into<SecurityIdPtr<'a>>>(&self, sid: S) -> Option<GroupAttributes> {
+        self.position(sid.into()).map(|index| self.groups[index].1)
+    }
+
+    pub fn contains<'a, S: Into<SecurityIdPtr<'a>>>(&self, sid: S) -> bool {
+        self.position(sid.into()).is_some()
+    }
+
+    pub fn is_enabled<'a, S: Into<SecurityIdPtr<'a>>>(&self, sid: S) -> bool {
+        let attributes = self.attributes(sid).unwrap_or_else(GroupAttributes::empty);
+
+        attributes.contains(GroupAttributes::ENABLED)
+            && !attributes.contains(GroupAttributes::USE_FOR_DENY_ONLY)
+    }
+
+    pub fn is_deny_only<'a, S: Into<SecurityIdPtr<'a>>>(&self, sid: S) -> bool {
+        let attributes = self.attributes(sid).unwrap_or_else(GroupAttributes::empty);
+
+        attributes.contains(GroupAttributes::USE_FOR_DENY_ONLY)
+    }
+
+    pub fn applies_to(&self, entry: &AccessControlEntryPtr) -> bool {
+        let trustee = match entry.trustee() {
+            Some(trustee) => trustee,
+            None => return false,
+        };
+
+        match entry.grants_access() {
+            Some(true) => self.is_enabled(trustee),
+            Some(false) => self.is_enabled(trustee) || self.is_deny_only(trustee),
+            None => false,
+        }
+    }
+
+    pub fn integrity_level(&self) -> Option<IntegrityLevel> {
+        self.iter()
+            .find(|&(_, attributes)| attributes.contains(GroupAttributes::INTEGRITY))
+            .and_then(|(sid, _)| sid.integrity_level())
+    }
+
+    pub fn logon_sid<'a>(&'a self) -> Option<SecurityIdPtr<'a>> {
+        self.iter()
+            .find(|&(_, attributes)| attributes.contains(GroupAttributes::LOGON_ID))
+            .map(|(sid, _)| sid)
+    }
+
+    pub fn iter<'a>(&'a self) -> Groups<'a> {
+        Groups {
+            groups: self.groups.iter(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    fn position(&self, sid: SecurityIdPtr) -> Option<usize> {
+        self.groups.iter().position(|(group, _)| *group == sid)
+    }
+}
+
+impl FromIterator<(SecurityId, GroupAttributes)> for TokenGroups {
+    fn from_iter<I>(groups: I) -> Self
+    where
+        I: IntoIterator<Item = (SecurityId, GroupAttributes)>,
+    {
+        let mut token_groups = TokenGroups::new();
+
+        token_groups.extend(groups);
+
+        token_groups
+    }
+}
+
+impl Extend<(SecurityId, GroupAttributes)> for TokenGroups {
+    fn extend<I>(&mut self, groups: I)
+    where
+        I: IntoIterator<Item = (SecurityId, GroupAttributes)>,
+    {
+        for (sid, attributes) in groups {
+            self.insert(sid, attributes);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a TokenGroups {
+    type Item = (SecurityIdPtr<'a>, GroupAttributes);
+    type IntoIter = Groups<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Groups<'a> {
+    groups: slice::Iter<'a, (SecurityId, GroupAttributes)>,
+}
+
+impl<'a> Iterator for Groups<'a> {
+    type Item = (SecurityIdPtr<'a>, GroupAttributes);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.groups
+            .next()
+            .map(|&(ref sid, attributes)| (sid.as_sid_ptr(), attributes))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.groups.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for Groups<'a> {}
+
+pub struct SidHistory<'a> {
+    sids: slice::Iter<'a, SecurityId>,
+}
+
+impl<'a> Iterator for SidHistory<'a> {
+    type Item = SecurityIdPtr<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.sids.next().map(SecurityId::as_sid_ptr)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sids.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for SidHistory<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {AccessControlEntry, AccessControlList, AccessMask, AceFlags, AceType};
+
+    fn acl(ace_type: AceType, trustee: &SecurityId) -> AccessControlList {
+        let entry = AccessControlEntry::builder(ace_type)
+            .flags(AceFlags::empty())
+            .mask(AccessMask::GENERIC_READ)
+            .trustee(trustee)
+            .build()
+            .unwrap();
+        let mut acl = AccessControlList::new();
+
+        acl.push(&entry).unwrap();
+        acl
+    }
+
+    fn old_account() -> SecurityId {
+        "S-1-5-21-1004336348-1177238915-682003330-1103"
+            .parse()
+            .unwrap()
+    }
+
+    fn applies(groups: &TokenGroups, acl: &AccessControlList) -> bool {
+        groups.applies_to(&acl.as_acl_ptr().entries().next().unwrap())
+    }
+
+    #[test]
+    fn deny_only_groups_are_not_enabled() {
+        let everyone: SecurityId = "S-1-1-0".parse().unwrap();
+        let users: SecurityId = "S-1-5-32-545".parse().unwrap();
+        let administrators: SecurityId = "S-1-5-32-544".parse().unwrap();
+        let disabled: SecurityId = "S-1-5-32-551".parse().unwrap();
+        let missing: SecurityId = "S-1-5-32-555".parse().unwrap();
+        let groups: TokenGroups = vec![
+            (
+                everyone.clone(),
+                GroupAttributes::MANDATORY | GroupAttributes::ENABLED,
+            ),
+            (
+                users.clone(),
+                GroupAttributes::ENABLED | GroupAttributes::USE_FOR_DENY_ONLY,
+            ),
+            (administrators.clone(), GroupAttributes::USE_FOR_DENY_ONLY),
+            (disabled.clone(), GroupAttributes::ENABLED_BY_DEFAULT),
+        ]
+        .into_iter()
+        .collect();
+
+        assert!(groups.is_enabled(&everyone));
+        assert!(!groups.is_enabled(&users));
+        assert!(!groups.is_enabled(&administrators));
+        assert!(!groups.is_enabled(&disabled));
+        assert!(!groups.is_enabled(&missing));
+        assert!(groups.is_deny_only(&users));
+        assert!(groups.is_deny_only(&administrators));
+        assert!(!groups.is_deny_only(&everyone));
+    }
+
+    #[test]
+    fn deny_only_groups_match_only_deny_entries() {
+        let everyone: SecurityId = "S-1-1-0".parse().unwrap();
+        let administrators: SecurityId = "S-1-5-32-544".parse().unwrap();
+        let disabled: SecurityId = "S-1-5-32-551".parse().unwrap();
+        let missing: SecurityId = "S-1-5-32-555".parse().unwrap();
+        let groups: TokenGroups = vec![
+            (everyone.clone(), GroupAttributes::ENABLED),
+            (administrators.clone(), GroupAttributes::USE_FOR_DENY_ONLY),
+            (disabled.clone(), GroupAttributes::MANDATORY),
+        ]
+        .into_iter()
+        .collect();
+
+        assert!(applies(&groups, &acl(AceType::AccessAllowed, &everyone)));
+        assert!(applies(&groups, &acl(AceType::AccessDenied, &everyone)));
+        assert!(!applies(
+            &groups,
+            &acl(AceType::AccessAllowed, &administrators)
+        ));
+        assert!(applies(
+            &groups,
+            &acl(AceType::AccessDenied, &administrators)
+        ));
+        assert!(!applies(&groups, &acl(AceType::AccessAllowed, &disabled)));
+        assert!(!applies(&groups, &acl(AceType::AccessDenied, &disabled)));
+        assert!(!applies(&groups, &acl(AceType::AccessDenied, &missing)));
+        assert!(!applies(&groups, &acl(AceType::SystemAudit, &everyone)));
+    }
+
+    #[test]
+    fn history_entries_match_trustees() {
+        let mut groups = TokenGroups::new();
+
+        groups.insert_history(old_account());
+
+        let allowed = acl(AceType::AccessAllowed, &old_account());
+        let denied = acl(AceType::AccessDenied, &old_account());
+
+        assert!(groups.is_history(&old_account()));
+        assert!(groups.is_enabled(&old_account()));
+        assert!(groups.applies_to(&allowed.as_acl_ptr().entries().next().unwrap()));
+        assert!(groups.applies_to(&denied.as_acl_ptr().entries().next().unwrap()));
+        assert_eq!(
+            groups.history().collect::<Vec<_>>(),
+            vec![old_account().as_sid_ptr()]
+        );
+    }
+
+    #[test]
+    fn history_keeps_existing_group_attributes() {
+        let mut groups = TokenGroups::new();
+
+        groups.insert(old_account(), GroupAttributes::USE_FOR_DENY_ONLY);
+        groups.insert_history(old_account());
+
+        let allowed = acl(AceType::AccessAllowed, &old_account());
+
+        assert!(groups.is_history(&old_account()));
+        assert!(groups.is_deny_only(&old_account()));
+        assert!(!groups.applies_to(&allowed.as_acl_ptr().entries().next().unwrap()));
+    }
+
+    #[test]
+    fn removing_a_group_clears_its_history() {
+        let mut groups = TokenGroups::new();
+
+        groups.insert_history(old_account());
+
+        assert!(groups.remove(&old_account()).is_some());
+        assert!(!groups.is_history(&old_account()));
+        assert_eq!(groups.history().len(), 0);
+    }
+}